@@ -0,0 +1,412 @@
+//! Binary cache for sparse view datasets.
+//!
+//! ## Format
+//!
+//! All the numbers are in little-endian order.
+//!
+//! 1. Magic bytes `GSVC` and the format version (`u32`).
+//! 2. Hash of the source (`u64`).
+//! 3. Cameras: the count (`u64`), then for each camera:
+//!    - Camera ID (`u32`), image ID (`u32`), sensor ID (`u32`) and view ID (`u32`).
+//!    - Image file path in UTF-8, prefixed by the byte length (`u64`).
+//!    - Encoded image, prefixed by the byte length (`u64`).
+//!    - Image width and height (`u32`), field of view x and y (`f64`).
+//!    - View position (`[f64; 3]`) and view transform (`[[f64; 4]; 4]`).
+//!    - Depth map flag (`u8`), then if it is `1`, the depth map width and
+//!      height (`u32`) and depths (`[f32]`).
+//! 4. Points: the count (`u64`), then for each point the color RGB (`[f64; 3]`)
+//!    and the position (`[f64; 3]`).
+//!
+//! The images are cached as [`Image::image_encoded`] held by the dataset,
+//! so the resized images are cached after resizing,
+//! and the cache stays as compact as the encoded images.
+
+pub use super::*;
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+/// Magic bytes of the cache.
+pub const CACHE_MAGIC: [u8; 4] = *b"GSVC";
+
+/// Format version of the cache.
+pub const CACHE_VERSION: u32 = 5;
+
+/// Cache operations
+impl SparseViewDataset {
+    /// Encode the dataset to the cache.
+    ///
+    /// `source_hash` identifies the source, e.g. from
+    /// [`SparseViewDataset::hash_source_files`].
+    pub fn encode_cache<W: Write>(
+        &self,
+        writer: &mut W,
+        source_hash: u64,
+    ) -> Result<&Self, Error> {
+        writer.write_all(&CACHE_MAGIC)?;
+        writer.write_all(&CACHE_VERSION.to_le_bytes())?;
+        writer.write_all(&source_hash.to_le_bytes())?;
+
+        writer.write_all(&(self.cameras.len() as u64).to_le_bytes())?;
+        for camera in self.cameras.values() {
+            let image = &camera.image;
+            let view = &camera.view;
+            let image_file_path = image.image_file_path.to_str().ok_or_else(|| {
+                Error::InvalidUtf8(format!("{:?}", image.image_file_path))
+            })?;
+
+            writer.write_all(&camera.camera_id.to_le_bytes())?;
+            writer.write_all(&image.image_id.to_le_bytes())?;
+            writer.write_all(&camera.sensor_id.to_le_bytes())?;
+            writer.write_all(&view.view_id.to_le_bytes())?;
+            encode_bytes(writer, image_file_path.as_bytes())?;
+            encode_bytes(writer, &image.image_encoded)?;
+            writer.write_all(&view.image_width.to_le_bytes())?;
+            writer.write_all(&view.image_height.to_le_bytes())?;
+            encode_f64s(writer, &[view.field_of_view_x, view.field_of_view_y])?;
+            encode_f64s(writer, &view.view_position)?;
+            encode_f64s(writer, &view.view_transform.concat())?;
+            match &camera.depth {
+                Some(depth) => {
+                    writer.write_all(&[1])?;
+                    writer.write_all(&depth.width.to_le_bytes())?;
+                    writer.write_all(&depth.height.to_le_bytes())?;
+                    depth
+                        .depths
+                        .iter()
+                        .try_for_each(|depth| writer.write_all(&depth.to_le_bytes()))?;
+                },
+                None => writer.write_all(&[0])?,
+            }
+        }
+
+        writer.write_all(&(self.points.len() as u64).to_le_bytes())?;
+        for point in &self.points {
+            encode_f64s(writer, &point.color_rgb)?;
+            encode_f64s(writer, &point.position)?;
+        }
+
+        writer.flush()?;
+        Ok(self)
+    }
+
+    /// Decode the dataset from the cache.
+    ///
+    /// It fails with [`Error::MismatchedCacheHash`] if the cache is stale,
+    /// i.e., `source_hash` differs from the one in the cache.
+    pub fn decode_cache<R: Read>(
+        reader: &mut R,
+        source_hash: u64,
+    ) -> Result<Self, Error> {
+        let magic = decode_array::<_, 4>(reader)?;
+        if magic != CACHE_MAGIC {
+            return Err(Error::InvalidCache(format!(
+                "unknown magic bytes {magic:?}"
+            )));
+        }
+        let version = u32::from_le_bytes(decode_array(reader)?);
+        if version != CACHE_VERSION {
+            return Err(Error::InvalidCache(format!("unknown version {version}")));
+        }
+        let source_hash_cached = u64::from_le_bytes(decode_array(reader)?);
+        if source_hash_cached != source_hash {
+            return Err(Error::MismatchedCacheHash(source_hash_cached, source_hash));
+        }
+
+        let camera_count = u64::from_le_bytes(decode_array(reader)?);
+        let mut cameras = Cameras::default();
+        for _ in 0..camera_count {
+            let camera_id = u32::from_le_bytes(decode_array(reader)?);
+            let image_id = u32::from_le_bytes(decode_array(reader)?);
+            let sensor_id = u32::from_le_bytes(decode_array(reader)?);
+            let view_id = u32::from_le_bytes(decode_array(reader)?);
+            let image_file_path = String::from_utf8(decode_bytes(reader)?)
+                .map_err(|error| Error::InvalidUtf8(error.to_string()))?
+                .into();
+            let image_encoded = decode_bytes(reader)?;
+            let image_width = u32::from_le_bytes(decode_array(reader)?);
+            let image_height = u32::from_le_bytes(decode_array(reader)?);
+            let [field_of_view_x, field_of_view_y] = decode_f64s(reader)?;
+            let view_position = decode_f64s(reader)?;
+            let view_transform = decode_f64s::<_, 16>(reader)?;
+            let view_transform =
+                [0, 1, 2, 3].map(|i| [0, 1, 2, 3].map(|j| view_transform[i * 4 + j]));
+            let depth = match decode_array::<_, 1>(reader)? {
+                [0] => None,
+                _ => {
+                    let width = u32::from_le_bytes(decode_array(reader)?);
+                    let height = u32::from_le_bytes(decode_array(reader)?);
+                    let depths = (0..width as u64 * height as u64)
+                        .map(|_| Ok(f32::from_le_bytes(decode_array(reader)?)))
+                        .collect::<Result<_, Error>>()?;
+                    Some(DepthMap {
+                        depths,
+                        height,
+                        width,
+                    })
+                },
+            };
+
+            let camera = Camera {
+                camera_id,
+                depth,
+                image: Image {
+                    image_encoded,
+                    image_file_path,
+                    image_id,
+                },
+                sensor_id,
+                view: View {
+                    field_of_view_x,
+                    field_of_view_y,
+                    image_height,
+                    image_width,
+                    view_id,
+                    view_position,
+                    view_transform,
+                },
+            };
+            cameras.insert(camera_id, camera);
+        }
+
+        let point_count = u64::from_le_bytes(decode_array(reader)?);
+        let points = (0..point_count)
+            .map(|_| {
+                Ok(Point {
+                    color_rgb: decode_f64s(reader)?,
+                    position: decode_f64s(reader)?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self { cameras, points })
+    }
+
+    /// Decode the dataset from the cache file at `path` if it is valid,
+    /// otherwise initialize the dataset with `init` and write the cache file.
+    ///
+    /// The stale or corrupted cache file is overwritten.
+    pub fn init_from_cache_or_else<F: FnOnce() -> Result<Self, Error>>(
+        path: impl AsRef<Path>,
+        source_hash: u64,
+        init: F,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        if path.is_file() {
+            let reader = &mut BufReader::new(File::open(path)?);
+            if let Ok(dataset) = Self::decode_cache(reader, source_hash) {
+                return Ok(dataset);
+            }
+
+            #[cfg(all(debug_assertions, not(test)))]
+            log::debug!(
+                target: "gausplat::trainer::dataset::sparse_view",
+                "SparseViewDataset::init_from_cache_or_else > invalid ({path:?})",
+            );
+        }
+
+        let dataset = init()?;
+        dataset.encode_cache(&mut BufWriter::new(File::create(path)?), source_hash)?;
+
+        Ok(dataset)
+    }
+
+    /// Compute a hash of the source files from their metadata.
+    ///
+    /// ## Details
+    ///
+    /// It hashes the path, the byte length and the modified time of each file
+    /// in the order of the paths with 64-bit FNV-1a, so it is stable across runs
+    /// without reading the files. Touching or moving a file invalidates the hash.
+    pub fn hash_source_files<P: AsRef<Path>, I: IntoIterator<Item = P>>(
+        paths: I
+    ) -> Result<u64, Error> {
+        const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+
+        let mut paths = paths
+            .into_iter()
+            .map(|path| path.as_ref().to_owned())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut hash = FNV_OFFSET;
+        let mut update = |bytes: &[u8]| {
+            bytes.iter().for_each(|byte| {
+                hash = (hash ^ *byte as u64).wrapping_mul(FNV_PRIME);
+            });
+        };
+
+        for path in paths {
+            let metadata = path.metadata()?;
+            let modified = metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos();
+            let path = path.to_string_lossy();
+            update(&(path.len() as u64).to_le_bytes());
+            update(path.as_bytes());
+            update(&metadata.len().to_le_bytes());
+            update(&modified.to_le_bytes());
+        }
+
+        Ok(hash)
+    }
+}
+
+#[inline]
+fn encode_bytes<W: Write>(
+    writer: &mut W,
+    bytes: &[u8],
+) -> Result<(), Error> {
+    writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+#[inline]
+fn encode_f64s<W: Write>(
+    writer: &mut W,
+    values: &[f64],
+) -> Result<(), Error> {
+    values
+        .iter()
+        .try_for_each(|value| writer.write_all(&value.to_le_bytes()))?;
+    Ok(())
+}
+
+#[inline]
+fn decode_array<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], Error> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[inline]
+fn decode_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    let len = u64::from_le_bytes(decode_array(reader)?);
+    let mut bytes = vec![];
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(Error::InvalidCache("unexpected end of bytes".into()));
+    }
+    Ok(bytes)
+}
+
+#[inline]
+fn decode_f64s<R: Read, const N: usize>(reader: &mut R) -> Result<[f64; N], Error> {
+    let mut values = [0.0; N];
+    for value in &mut values {
+        *value = f64::from_le_bytes(decode_array(reader)?);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn encode_and_decode_cache() {
+        use super::*;
+
+        let mut image_encoded = vec![];
+        image::RgbImage::from_raw(2, 1, vec![255, 0, 128, 0, 64, 32])
+            .unwrap()
+            .write_to(
+                &mut std::io::Cursor::new(&mut image_encoded),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        let camera = Camera {
+            camera_id: 3,
+            depth: Some(DepthMap {
+                depths: vec![1.0, 2.0],
+                height: 1,
+                width: 2,
+            }),
+            image: Image {
+                image_encoded,
+                image_file_path: "images/00003.png".into(),
+                image_id: 3,
+            },
+            sensor_id: 1,
+            view: View {
+                field_of_view_x: 1.25,
+                field_of_view_y: 0.75,
+                image_height: 6,
+                image_width: 8,
+                view_id: 3,
+                view_position: [-1.0, -2.0, -3.0],
+                view_transform: [
+                    [1.0, 0.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0, 0.0],
+                    [0.0, 0.0, 1.0, 0.0],
+                    [1.0, 2.0, 3.0, 1.0],
+                ],
+            },
+        };
+        let target = SparseViewDataset {
+            cameras: [(3, camera)].into_iter().collect(),
+            points: vec![Point {
+                color_rgb: [1.0, 0.0, 0.2],
+                position: [0.5, 0.25, 0.125],
+            }],
+        };
+
+        let mut cache = vec![];
+        target.encode_cache(&mut cache, 0x1234).unwrap();
+
+        let output =
+            SparseViewDataset::decode_cache(&mut cache.as_slice(), 0x1234).unwrap();
+        assert_eq!(output, target);
+
+        let error = SparseViewDataset::decode_cache(&mut cache.as_slice(), 0x4321);
+        assert!(
+            matches!(error, Err(Error::MismatchedCacheHash(0x1234, 0x4321))),
+            "{error:?}"
+        );
+
+        let error =
+            SparseViewDataset::decode_cache(&mut &cache[..cache.len() - 1], 0x1234);
+        assert!(error.is_err(), "{error:?}");
+
+        let error = SparseViewDataset::decode_cache(&mut b"ply\n".as_slice(), 0x1234);
+        assert!(matches!(error, Err(Error::InvalidCache(_))), "{error:?}");
+    }
+
+    #[test]
+    fn hash_source_files() {
+        use super::*;
+
+        let directory = std::env::temp_dir().join(format!(
+            "gausplat-trainer-hash-source-files-{}",
+            std::process::id()
+        ));
+        let path = directory.join("source");
+        let path_other = directory.join("source-other");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(&path, b"source").unwrap();
+        std::fs::write(&path_other, b"source").unwrap();
+
+        let target = SparseViewDataset::hash_source_files([&path]).unwrap();
+        let output = SparseViewDataset::hash_source_files([&path]).unwrap();
+        assert_eq!(output, target);
+
+        // The hash depends on the paths
+        let output = SparseViewDataset::hash_source_files([&path_other]).unwrap();
+        assert_ne!(output, target);
+
+        std::fs::write(&path, b"source modified").unwrap();
+        let output = SparseViewDataset::hash_source_files([&path]).unwrap();
+        assert_ne!(output, target);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+        SparseViewDataset::hash_source_files([&path]).unwrap_err();
+    }
+}
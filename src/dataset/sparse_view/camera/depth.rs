@@ -0,0 +1,655 @@
+//! Depth map for a sparse view camera.
+//!
+//! The supported formats are:
+//! - 16-bit (or 8-bit) grayscale PNG, in units of `scale`.
+//! - Portable float map (PFM).
+//! - NumPy array (NPY) of `float32` or `float64` with shape `[H, W]`.
+//!
+//! Only the loading and the alignment to the sparse points are provided.
+//! The trainer does not supervise the depths yet,
+//! since the renderer does not output the depths.
+
+pub use super::*;
+pub use burn::tensor::{backend::Backend, Tensor, TensorData};
+
+use crate::{dataset::SparseViewDataset, error::Error};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A depth map in view space.
+///
+/// The non-positive depths are invalid.
+#[derive(Clone, Default, PartialEq)]
+pub struct DepthMap {
+    /// Depths in row-major order with length of `height * width`.
+    pub depths: Vec<f32>,
+    /// Height in pixels.
+    pub height: u32,
+    /// Width in pixels.
+    pub width: u32,
+}
+
+impl DepthMap {
+    /// Read the depth map from the file.
+    ///
+    /// The format is specified by the file extension (`png`, `pfm` or `npy`).
+    /// The PNG values are multiplied by `scale`.
+    pub fn read(
+        path: impl AsRef<Path>,
+        scale: f32,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "png" => Self::decode_png(&bytes, scale),
+            "pfm" => Self::decode_pfm(&bytes),
+            "npy" => Self::decode_npy(&bytes),
+            _ => Err(Error::InvalidDepth(format!(
+                "unknown file extension {path:?}"
+            ))),
+        }
+    }
+
+    /// Decode the depth map from the grayscale PNG.
+    ///
+    /// The 8-bit and 16-bit values are both multiplied by `scale` as they are.
+    pub fn decode_png(
+        bytes: &[u8],
+        scale: f32,
+    ) -> Result<Self, Error> {
+        let image = image::load_from_memory(bytes)?;
+        let (width, height) = (image.width(), image.height());
+        // NOTE: `DynamicImage::to_luma16` would multiply the 8-bit values by 257.
+        let depths = match image {
+            image::DynamicImage::ImageLuma8(image) => image
+                .into_raw()
+                .into_iter()
+                .map(|depth| depth as f32 * scale)
+                .collect(),
+            image => image
+                .to_luma16()
+                .into_raw()
+                .into_iter()
+                .map(|depth| depth as f32 * scale)
+                .collect(),
+        };
+        Ok(Self {
+            depths,
+            height,
+            width,
+        })
+    }
+
+    /// Decode the depth map from the portable float map (PFM).
+    ///
+    /// Only the first channel of the color PFM is used.
+    pub fn decode_pfm(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |message: &str| Error::InvalidDepth(format!("PFM: {message}"));
+
+        // NOTE: The header has three tokens separated by whitespaces: "Pf W H SCALE".
+        let mut tokens = Vec::with_capacity(4);
+        let mut offset = 0;
+        while tokens.len() < 4 {
+            while bytes.get(offset).is_some_and(u8::is_ascii_whitespace) {
+                offset += 1;
+            }
+            let start = offset;
+            while bytes.get(offset).is_some_and(|b| !b.is_ascii_whitespace()) {
+                offset += 1;
+            }
+            if start == offset {
+                return Err(invalid("the header is incomplete"));
+            }
+            tokens.push(String::from_utf8_lossy(&bytes[start..offset]).into_owned());
+        }
+        // Skipping a single whitespace after the header
+        offset += 1;
+
+        let channel_count = match tokens[0].as_str() {
+            "Pf" => 1,
+            "PF" => 3,
+            _ => return Err(invalid("the magic number is not `Pf` or `PF`")),
+        };
+        let parse_dim = |token: &str| token.parse::<u32>().map_err(|_| invalid(token));
+        let width = parse_dim(&tokens[1])?;
+        let height = parse_dim(&tokens[2])?;
+        let is_little_endian =
+            tokens[3].parse::<f64>().map_err(|_| invalid(&tokens[3]))? < 0.0;
+
+        let body_len = get_depth_count(width, height)
+            .map_err(invalid)?
+            .checked_mul(channel_count * 4)
+            .ok_or_else(|| invalid("the dimensions are too large"))?;
+        let body = offset
+            .checked_add(body_len)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or_else(|| invalid("the data is incomplete"))?;
+        let values = body
+            .chunks_exact(4)
+            .map(|chunk| {
+                let chunk = chunk.try_into().unwrap();
+                if is_little_endian {
+                    f32::from_le_bytes(chunk)
+                } else {
+                    f32::from_be_bytes(chunk)
+                }
+            })
+            .step_by(channel_count)
+            .collect::<Vec<_>>();
+
+        // NOTE: The rows of PFM are from bottom to top.
+        let depths = values
+            .chunks_exact(width as usize)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+
+        Ok(Self {
+            depths,
+            height,
+            width,
+        })
+    }
+
+    /// Decode the depth map from the NumPy array (NPY).
+    pub fn decode_npy(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |message: &str| Error::InvalidDepth(format!("NPY: {message}"));
+
+        if !bytes.starts_with(b"\x93NUMPY") || bytes.len() < 10 {
+            return Err(invalid("the magic string is not `\\x93NUMPY`"));
+        }
+        let (header_len, header_offset) = match bytes[6] {
+            1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+            _ => {
+                let len = bytes
+                    .get(8..12)
+                    .ok_or_else(|| invalid("the header is incomplete"))?;
+                (u32::from_le_bytes(len.try_into().unwrap()) as usize, 12)
+            },
+        };
+        let header = bytes
+            .get(header_offset..header_offset + header_len)
+            .ok_or_else(|| invalid("the header is incomplete"))?;
+        let header = String::from_utf8_lossy(header);
+
+        if header.contains("'fortran_order': True") {
+            return Err(invalid("the fortran order is not supported"));
+        }
+        let value_size = if header.contains("'<f4'") {
+            4
+        } else if header.contains("'<f8'") {
+            8
+        } else {
+            return Err(invalid("the data type is not `<f4` or `<f8`"));
+        };
+        let shape = header
+            .split_once("'shape':")
+            .and_then(|(_, rest)| rest.split_once('('))
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .map(|(shape, _)| {
+                shape
+                    .split(',')
+                    .filter(|dim| !dim.trim().is_empty())
+                    .map(|dim| dim.trim().parse::<u32>())
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .and_then(Result::ok)
+            .ok_or_else(|| invalid("the shape is invalid"))?;
+        let [height, width] = shape[..] else {
+            return Err(invalid("the shape is not 2D"));
+        };
+
+        let body_offset = header_offset + header_len;
+        let body_len = get_depth_count(width, height)
+            .map_err(invalid)?
+            .checked_mul(value_size)
+            .ok_or_else(|| invalid("the dimensions are too large"))?;
+        let body = body_offset
+            .checked_add(body_len)
+            .and_then(|end| bytes.get(body_offset..end))
+            .ok_or_else(|| invalid("the data is incomplete"))?;
+        let depths = body
+            .chunks_exact(value_size)
+            .map(|chunk| match value_size {
+                4 => f32::from_le_bytes(chunk.try_into().unwrap()),
+                _ => f64::from_le_bytes(chunk.try_into().unwrap()) as f32,
+            })
+            .collect();
+
+        Ok(Self {
+            depths,
+            height,
+            width,
+        })
+    }
+
+    /// Decode the depth map to a tensor with shape `[H, W]`.
+    pub fn decode_depth_tensor<B: Backend>(
+        &self,
+        device: &B::Device,
+    ) -> Tensor<B, 2> {
+        Tensor::from_data(
+            TensorData::new(
+                self.depths.to_owned(),
+                [self.height as usize, self.width as usize],
+            ),
+            device,
+        )
+    }
+
+    /// Return the depth at the pixel or `None` if it is out of bounds or invalid.
+    #[inline]
+    pub fn get(
+        &self,
+        x: u32,
+        y: u32,
+    ) -> Option<f32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.depths
+            .get((y as u64 * self.width as u64 + x as u64) as usize)
+            .copied()
+            .filter(|depth| *depth > 0.0)
+    }
+
+    /// Resize the depth map using the nearest neighbor sampling.
+    ///
+    /// It fails with [`Error::InvalidDepth`] if the depth count
+    /// is not `width * height` or the depth map to resize is empty.
+    pub fn resize(
+        &mut self,
+        width: u32,
+        height: u32,
+    ) -> Result<&mut Self, Error> {
+        if width == self.width && height == self.height {
+            return Ok(self);
+        }
+
+        let count = self.width as u64 * self.height as u64;
+        if self.depths.len() as u64 != count {
+            return Err(Error::InvalidDepth(format!(
+                "the depth count {} is not {}x{}",
+                self.depths.len(),
+                self.width,
+                self.height,
+            )));
+        }
+        if count == 0 && width as u64 * height as u64 != 0 {
+            return Err(Error::InvalidDepth(
+                "the depth map to resize is empty".into(),
+            ));
+        }
+
+        let width_source = self.width as u64;
+        let height_source = self.height as u64;
+        let depths = (0..height as u64)
+            .flat_map(|y| {
+                let y_source = y * height_source / height as u64;
+                (0..width as u64).map(move |x| {
+                    let x_source = x * width_source / width as u64;
+                    y_source * width_source + x_source
+                })
+            })
+            .map(|index| self.depths[index as usize])
+            .collect();
+
+        self.depths = depths;
+        self.height = height;
+        self.width = width;
+        Ok(self)
+    }
+
+    /// Apply the affine transformation `scale * depth + shift` to the valid depths.
+    pub fn transform(
+        &mut self,
+        scale: f32,
+        shift: f32,
+    ) -> &mut Self {
+        self.depths
+            .iter_mut()
+            .filter(|depth| **depth > 0.0)
+            .for_each(|depth| *depth = scale * *depth + shift);
+        self
+    }
+}
+
+/// Depth operations
+impl Camera {
+    /// Return the paths of the depth file candidates in `directory`.
+    ///
+    /// The candidates have the same file stem as the image file.
+    pub fn depth_file_paths(
+        &self,
+        directory: impl AsRef<Path>,
+    ) -> Vec<PathBuf> {
+        let directory = directory.as_ref();
+        let stem = self.image.image_file_path.file_stem().unwrap_or_default();
+        ["png", "pfm", "npy"]
+            .into_iter()
+            .map(|extension| {
+                // NOTE: The dots in the stem are kept, unlike `Path::with_extension`.
+                let mut file_name = stem.to_owned();
+                file_name.push(".");
+                file_name.push(extension);
+                directory.join(file_name)
+            })
+            .collect()
+    }
+
+    /// Project the position (world space) to the pixel coordinates.
+    ///
+    /// ## Returns
+    ///
+    /// The pixel coordinates and the depth, or `None` if it is behind the camera.
+    pub fn project(
+        &self,
+        position: &[f64; 3],
+    ) -> Option<([f64; 2], f64)> {
+        let [x, y, z] = self.to_view_position(position);
+        if z <= 0.0 {
+            return None;
+        }
+        let [focal_length_x, focal_length_y] = self.focal_lengths();
+        let pixel = [
+            focal_length_x * x / z + self.view.image_width as f64 / 2.0,
+            focal_length_y * y / z + self.view.image_height as f64 / 2.0,
+        ];
+        Some((pixel, z))
+    }
+}
+
+/// Depth operations
+impl SparseViewDataset {
+    /// Read the depth maps of the cameras from `directory`.
+    ///
+    /// For each camera, the first existing file of
+    /// [`Camera::depth_file_paths`] is read and resized to the view.
+    /// The PNG values are multiplied by `scale`.
+    ///
+    /// ## Returns
+    ///
+    /// The count of cameras with depth maps read.
+    pub fn read_depths(
+        &mut self,
+        directory: impl AsRef<Path>,
+        scale: f32,
+    ) -> Result<usize, Error> {
+        let directory = directory.as_ref();
+        let mut count = 0;
+
+        for camera in self.cameras.values_mut() {
+            let Some(path) = camera
+                .depth_file_paths(directory)
+                .into_iter()
+                .find(|path| path.is_file())
+            else {
+                continue;
+            };
+
+            let mut depth = DepthMap::read(path, scale)?;
+            depth.resize(camera.view.image_width, camera.view.image_height)?;
+            camera.depth = Some(depth);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Align the depth maps to the points visible in each view
+    /// by the least squares fit of scale and shift.
+    ///
+    /// The depth map with fewer than two samples is left unchanged.
+    ///
+    /// ## Returns
+    ///
+    /// The scale and shift applied for each camera ID.
+    pub fn align_depths(&mut self) -> Vec<(u32, [f32; 2])> {
+        let points = &self.points;
+
+        self.cameras
+            .values_mut()
+            .filter_map(|camera| {
+                let depth = camera.depth.as_ref()?;
+
+                // Sampling the pairs of (prior, target)
+                let samples = points
+                    .iter()
+                    .filter_map(|point| {
+                        let ([x, y], target) = camera.project(&point.position)?;
+                        if x < 0.0 || y < 0.0 {
+                            return None;
+                        }
+                        let prior = depth.get(x as u32, y as u32)?;
+                        Some((prior as f64, target))
+                    })
+                    .collect::<Vec<_>>();
+                let [scale, shift] = fit_scale_and_shift(&samples)?.map(|v| v as f32);
+
+                camera.depth.as_mut()?.transform(scale, shift);
+                Some((camera.camera_id, [scale, shift]))
+            })
+            .collect()
+    }
+}
+
+/// Fit `target = scale * prior + shift` by least squares.
+///
+/// ## Returns
+///
+/// `[scale, shift]`, or `None` if the fit is degenerate.
+pub fn fit_scale_and_shift(samples: &[(f64, f64)]) -> Option<[f64; 2]> {
+    let count = samples.len() as f64;
+    if samples.len() < 2 {
+        return None;
+    }
+
+    let mean_prior = samples.iter().map(|s| s.0).sum::<f64>() / count;
+    let mean_target = samples.iter().map(|s| s.1).sum::<f64>() / count;
+    let (covariance, variance) =
+        samples
+            .iter()
+            .fold((0.0, 0.0), |(covariance, variance), s| {
+                let d = s.0 - mean_prior;
+                (covariance + d * (s.1 - mean_target), variance + d * d)
+            });
+    if variance <= f64::EPSILON {
+        return None;
+    }
+
+    let scale = covariance / variance;
+    let shift = mean_target - scale * mean_prior;
+
+    Some([scale, shift])
+}
+
+impl std::fmt::Debug for DepthMap {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.debug_struct("DepthMap")
+            .field("depths.len()", &self.depths.len())
+            .field("height", &self.height)
+            .field("width", &self.width)
+            .finish()
+    }
+}
+
+/// Return the count of depths, which should be positive and fit in `u32`.
+#[inline]
+fn get_depth_count(
+    width: u32,
+    height: u32,
+) -> Result<usize, &'static str> {
+    match width.checked_mul(height) {
+        Some(0) => Err("the dimensions are zero"),
+        Some(count) => Ok(count as usize),
+        None => Err("the dimensions are too large"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn decode_pfm() {
+        use super::*;
+
+        let mut bytes = b"Pf\n2 2\n-1.0\n".to_vec();
+        [1.0_f32, 2.0, 3.0, 4.0]
+            .iter()
+            .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()));
+
+        let target = DepthMap {
+            depths: vec![3.0, 4.0, 1.0, 2.0],
+            height: 2,
+            width: 2,
+        };
+        let output = DepthMap::decode_pfm(&bytes).unwrap();
+        assert_eq!(output, target);
+
+        DepthMap::decode_pfm(&bytes[..bytes.len() - 1]).unwrap_err();
+
+        for header in [b"Pf\n0 2\n-1.0\n".as_slice(), b"Pf\n65536 65536\n-1.0\n"] {
+            let error = DepthMap::decode_pfm(header);
+            assert!(matches!(error, Err(Error::InvalidDepth(_))), "{error:?}");
+        }
+    }
+
+    #[test]
+    fn decode_npy() {
+        use super::*;
+
+        let header = "{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }";
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        (0..6).for_each(|v| bytes.extend_from_slice(&(v as f64).to_le_bytes()));
+
+        let target = DepthMap {
+            depths: vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0],
+            height: 2,
+            width: 3,
+        };
+        let output = DepthMap::decode_npy(&bytes).unwrap();
+        assert_eq!(output, target);
+
+        DepthMap::decode_npy(b"\x93NUMPY").unwrap_err();
+
+        let header =
+            "{'descr': '<f4', 'fortran_order': False, 'shape': (65536, 65536), }";
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        let error = DepthMap::decode_npy(&bytes);
+        assert!(matches!(error, Err(Error::InvalidDepth(_))), "{error:?}");
+    }
+
+    #[test]
+    fn depth_file_paths() {
+        use super::*;
+
+        let camera = Camera {
+            image: Image {
+                image_file_path: "images/frame.0001.jpg".into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let target = ["frame.0001.png", "frame.0001.pfm", "frame.0001.npy"]
+            .map(|name| Path::new("depths").join(name))
+            .to_vec();
+        let output = camera.depth_file_paths("depths");
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn decode_png() {
+        use super::*;
+
+        let image =
+            image::ImageBuffer::<image::Luma<u16>, _>::from_raw(2, 1, vec![2_u16, 5])
+                .unwrap();
+        let mut bytes = vec![];
+        image
+            .write_to(
+                &mut std::io::Cursor::new(&mut bytes),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        let target = DepthMap {
+            depths: vec![1.0, 2.5],
+            height: 1,
+            width: 2,
+        };
+        let output = DepthMap::decode_png(&bytes, 0.5).unwrap();
+        assert_eq!(output, target);
+
+        let image = image::GrayImage::from_raw(2, 1, vec![2, 5]).unwrap();
+        let mut bytes = vec![];
+        image
+            .write_to(
+                &mut std::io::Cursor::new(&mut bytes),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        let output = DepthMap::decode_png(&bytes, 0.5).unwrap();
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn resize_and_transform() {
+        use super::*;
+
+        let mut depth = DepthMap {
+            depths: vec![1.0, 2.0, 3.0, 0.0],
+            height: 2,
+            width: 2,
+        };
+        depth.resize(4, 2).unwrap().transform(2.0, 1.0);
+
+        let target = vec![3.0, 3.0, 5.0, 5.0, 7.0, 7.0, 0.0, 0.0];
+        assert_eq!(depth.depths, target);
+        assert_eq!(depth.get(0, 0), Some(3.0));
+        assert_eq!(depth.get(3, 1), None);
+        assert_eq!(depth.get(4, 0), None);
+
+        let error = DepthMap::default().resize(2, 2);
+        assert!(matches!(error, Err(Error::InvalidDepth(_))), "{error:?}");
+
+        let mut depth = DepthMap {
+            depths: vec![1.0],
+            height: 2,
+            width: 2,
+        };
+        let error = depth.resize(4, 4);
+        assert!(matches!(error, Err(Error::InvalidDepth(_))), "{error:?}");
+        assert_eq!(depth.get(1, 1), None);
+    }
+
+    #[test]
+    fn fit_scale_and_shift() {
+        use super::*;
+
+        let samples = [(1.0, 3.0), (2.0, 5.0), (4.0, 9.0)];
+        let target = Some([2.0, 1.0]);
+        let output = super::fit_scale_and_shift(&samples);
+        assert_eq!(output, target);
+
+        let target = None;
+        let output = super::fit_scale_and_shift(&samples[..1]);
+        assert_eq!(output, target);
+    }
+}
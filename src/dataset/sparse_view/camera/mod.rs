@@ -1,8 +1,10 @@
 //! Sparse view camera module.
 
 pub mod cameras;
+pub mod depth;
 
 pub use cameras::*;
+pub use depth::*;
 pub use gausplat_loader::source::image::*;
 pub use gausplat_renderer::render::view::*;
 
@@ -13,8 +15,17 @@ pub struct Camera {
     ///
     /// This is the same as the image ID and view ID.
     pub camera_id: u32,
+    /// Depth map prior.
+    ///
+    /// It is not used in training yet, see [`depth`].
+    pub depth: Option<DepthMap>,
     /// Image.
     pub image: Image,
+    /// Sensor ID.
+    ///
+    /// The images captured by the same physical sensor share the sensor ID.
+    /// It is the same as the camera ID in COLMAP model.
+    pub sensor_id: u32,
     /// View.
     pub view: View,
 }
@@ -29,6 +40,9 @@ impl Camera {
     ) -> Result<&mut Self, Error> {
         self.image.resize_max(to)?;
         self.view.resize_max(to);
+        if let Some(depth) = &mut self.depth {
+            depth.resize(self.view.image_width, self.view.image_height)?;
+        }
         Ok(self)
     }
 
@@ -38,3 +52,87 @@ impl Camera {
         self.view.image_height.max(self.view.image_width)
     }
 }
+
+/// Geometry operations
+impl Camera {
+    /// Return the view rotation (world to view) in row-major order.
+    ///
+    /// ## Details
+    ///
+    /// [`View::view_transform`] is stored in column-major order.
+    #[inline]
+    pub fn view_rotation(&self) -> [[f64; 3]; 3] {
+        let t = &self.view.view_transform;
+        [
+            [t[0][0], t[1][0], t[2][0]],
+            [t[0][1], t[1][1], t[2][1]],
+            [t[0][2], t[1][2], t[2][2]],
+        ]
+    }
+
+    /// Return the view translation (world to view).
+    #[inline]
+    pub fn view_translation(&self) -> [f64; 3] {
+        let t = &self.view.view_transform;
+        [t[3][0], t[3][1], t[3][2]]
+    }
+
+    /// Transform the position from world space to view space.
+    pub fn to_view_position(
+        &self,
+        position: &[f64; 3],
+    ) -> [f64; 3] {
+        let r = self.view_rotation();
+        let t = self.view_translation();
+        [0, 1, 2].map(|i| {
+            r[i][0] * position[0] + r[i][1] * position[1] + r[i][2] * position[2] + t[i]
+        })
+    }
+
+    /// Check if the position (world space) is inside the view frustum
+    /// bounded by `depth_near` and `depth_far`.
+    pub fn is_in_frustum(
+        &self,
+        position: &[f64; 3],
+        depth_near: f64,
+        depth_far: f64,
+    ) -> bool {
+        let [x, y, z] = self.to_view_position(position);
+        let tan_fov_x_half = (self.view.field_of_view_x / 2.0).tan();
+        let tan_fov_y_half = (self.view.field_of_view_y / 2.0).tan();
+
+        z >= depth_near
+            && z <= depth_far
+            && x.abs() <= z * tan_fov_x_half
+            && y.abs() <= z * tan_fov_y_half
+    }
+
+    /// Return the corners (world space) of the view frustum
+    /// bounded by `depth_near` and `depth_far`.
+    pub fn frustum_corners(
+        &self,
+        depth_near: f64,
+        depth_far: f64,
+    ) -> [[f64; 3]; 8] {
+        let r = self.view_rotation();
+        let t = self.view_translation();
+        let tan_fov_x_half = (self.view.field_of_view_x / 2.0).tan();
+        let tan_fov_y_half = (self.view.field_of_view_y / 2.0).tan();
+
+        let mut corners = [[0.0; 3]; 8];
+        corners.iter_mut().enumerate().for_each(|(index, corner)| {
+            let z = if index & 4 == 0 {
+                depth_near
+            } else {
+                depth_far
+            };
+            let x = if index & 1 == 0 { -z } else { z } * tan_fov_x_half;
+            let y = if index & 2 == 0 { -z } else { z } * tan_fov_y_half;
+            // p_world = R^T * (p_view - t)
+            let p = [x - t[0], y - t[1], z - t[2]];
+            *corner = [0, 1, 2].map(|i| r[0][i] * p[0] + r[1][i] * p[1] + r[2][i] * p[2]);
+        });
+
+        corners
+    }
+}
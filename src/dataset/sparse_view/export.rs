@@ -0,0 +1,356 @@
+//! Exporting sparse view datasets.
+//!
+//! The supported formats are:
+//! - COLMAP text format (`cameras.txt`, `images.txt` and `points3D.txt`).
+//! - Inria `cameras.json`, which the common 3DGS web viewers read.
+
+pub use super::*;
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// Export operations
+impl SparseViewDataset {
+    /// Write the dataset to the directory in COLMAP text format.
+    ///
+    /// The files `cameras.txt`, `images.txt` and `points3D.txt` are created.
+    pub fn write_colmap_text(
+        &self,
+        directory: impl AsRef<Path>,
+    ) -> Result<&Self, Error> {
+        let directory = directory.as_ref();
+        let create = |name: &str| File::create(directory.join(name)).map(BufWriter::new);
+
+        self.encode_colmap_cameras_text(&mut create("cameras.txt")?)?;
+        self.encode_colmap_images_text(&mut create("images.txt")?)?;
+        self.encode_colmap_points_text(&mut create("points3D.txt")?)?;
+
+        Ok(self)
+    }
+
+    /// Encode the sensors in COLMAP text format (`cameras.txt`).
+    ///
+    /// Each sensor is exported as a `PINHOLE` camera model keyed by
+    /// [`Camera::sensor_id`], with the intrinsics of its first camera.
+    pub fn encode_colmap_cameras_text<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<&Self, Error> {
+        let camera_ids_by_sensor = self.camera_ids_by_sensor();
+
+        writeln!(writer, "# Camera list with one line of data per camera:")?;
+        writeln!(writer, "#   CAMERA_ID, MODEL, WIDTH, HEIGHT, PARAMS[]")?;
+        writeln!(
+            writer,
+            "# Number of cameras: {}",
+            camera_ids_by_sensor.len()
+        )?;
+
+        for (sensor_id, camera_ids) in camera_ids_by_sensor {
+            let camera = &self.cameras[&camera_ids[0]];
+            let view = &camera.view;
+            let [focal_length_x, focal_length_y] = camera.focal_lengths();
+            writeln!(
+                writer,
+                "{} PINHOLE {} {} {} {} {} {}",
+                sensor_id,
+                view.image_width,
+                view.image_height,
+                focal_length_x,
+                focal_length_y,
+                view.image_width as f64 / 2.0,
+                view.image_height as f64 / 2.0,
+            )?;
+        }
+
+        writer.flush()?;
+        Ok(self)
+    }
+
+    /// Encode the images in COLMAP text format (`images.txt`).
+    ///
+    /// The camera ID of each image is [`Camera::sensor_id`],
+    /// and the 2D points of each image are left empty.
+    pub fn encode_colmap_images_text<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<&Self, Error> {
+        writeln!(writer, "# Image list with two lines of data per image:")?;
+        writeln!(
+            writer,
+            "#   IMAGE_ID, QW, QX, QY, QZ, TX, TY, TZ, CAMERA_ID, NAME"
+        )?;
+        writeln!(writer, "#   POINTS2D[] as (X, Y, POINT3D_ID)")?;
+        writeln!(writer, "# Number of images: {}", self.cameras.len())?;
+
+        for camera in self.cameras.values() {
+            let [qx, qy, qz, qw] = quaternion_from_matrix(&camera.view_rotation());
+            let [tx, ty, tz] = camera.view_translation();
+            writeln!(
+                writer,
+                "{} {qw} {qx} {qy} {qz} {tx} {ty} {tz} {} {}\n",
+                camera.image.image_id,
+                camera.sensor_id,
+                camera.image_file_name()?,
+            )?;
+        }
+
+        writer.flush()?;
+        Ok(self)
+    }
+
+    /// Encode the points in COLMAP text format (`points3D.txt`).
+    ///
+    /// The errors are zero and the tracks are left empty.
+    pub fn encode_colmap_points_text<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<&Self, Error> {
+        writeln!(writer, "# 3D point list with one line of data per point:")?;
+        writeln!(
+            writer,
+            "#   POINT3D_ID, X, Y, Z, R, G, B, ERROR, TRACK[] as (IMAGE_ID, POINT2D_IDX)"
+        )?;
+        writeln!(writer, "# Number of points: {}", self.points.len())?;
+
+        for (index, point) in self.points.iter().enumerate() {
+            let [x, y, z] = point.position;
+            let [r, g, b] = point
+                .color_rgb
+                .map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8);
+            writeln!(writer, "{} {x} {y} {z} {r} {g} {b} 0", index + 1)?;
+        }
+
+        writer.flush()?;
+        Ok(self)
+    }
+
+    /// Encode the cameras in Inria `cameras.json` format.
+    ///
+    /// The non-finite values are `null`.
+    pub fn encode_cameras_json<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<&Self, Error> {
+        write!(writer, "[")?;
+
+        for (index, camera) in self.cameras.values().enumerate() {
+            let view = &camera.view;
+            let [focal_length_x, focal_length_y] = camera.focal_lengths();
+            let name = camera.image_file_name()?;
+            let name = Path::new(name)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or(name);
+            // NOTE: The rotation is from view space to world space.
+            let rotation = camera.view_rotation();
+            let rotation = [0, 1, 2].map(|i| [0, 1, 2].map(|j| rotation[j][i]));
+            let [px, py, pz] = view.view_position.map(encode_json_number);
+
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+            write!(
+                writer,
+                "\n  {{\"id\": {index}, \"img_name\": {}, \
+                \"width\": {}, \"height\": {}, \
+                \"position\": [{px}, {py}, {pz}], \"rotation\": [",
+                encode_json_string(name),
+                view.image_width,
+                view.image_height,
+            )?;
+            for (i, [r0, r1, r2]) in rotation
+                .map(|r| r.map(encode_json_number))
+                .into_iter()
+                .enumerate()
+            {
+                let separator = if i > 0 { ", " } else { "" };
+                write!(writer, "{separator}[{r0}, {r1}, {r2}]")?;
+            }
+            write!(
+                writer,
+                "], \"fy\": {}, \"fx\": {}}}",
+                encode_json_number(focal_length_y),
+                encode_json_number(focal_length_x),
+            )?;
+        }
+
+        writeln!(writer, "\n]")?;
+        writer.flush()?;
+        Ok(self)
+    }
+}
+
+/// Export operations
+impl Camera {
+    /// Return the focal lengths in pixels.
+    #[inline]
+    pub fn focal_lengths(&self) -> [f64; 2] {
+        let view = &self.view;
+        [
+            view.image_width as f64 / (view.field_of_view_x / 2.0).tan() / 2.0,
+            view.image_height as f64 / (view.field_of_view_y / 2.0).tan() / 2.0,
+        ]
+    }
+
+    /// Return the image file name in UTF-8.
+    pub fn image_file_name(&self) -> Result<&str, Error> {
+        let path = &self.image.image_file_path;
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        name.to_str()
+            .ok_or_else(|| Error::InvalidUtf8(name.to_string_lossy().into()))
+    }
+}
+
+#[inline]
+fn encode_json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".into()
+    }
+}
+
+fn encode_json_string(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len() + 2);
+    encoded.push('"');
+    value.chars().for_each(|c| match c {
+        '"' => encoded.push_str("\\\""),
+        '\\' => encoded.push_str("\\\\"),
+        c if c.is_control() => encoded.push_str(&format!("\\u{:04x}", c as u32)),
+        c => encoded.push(c),
+    });
+    encoded.push('"');
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    fn dataset() -> super::SparseViewDataset {
+        use super::*;
+
+        let camera = Camera {
+            camera_id: 3,
+            depth: None,
+            image: Image {
+                image_file_path: "images/00003.png".into(),
+                image_id: 3,
+                ..Default::default()
+            },
+            sensor_id: 1,
+            view: View {
+                field_of_view_x: std::f64::consts::FRAC_PI_2,
+                field_of_view_y: std::f64::consts::FRAC_PI_2,
+                image_height: 6,
+                image_width: 8,
+                view_id: 3,
+                view_position: [-1.0, -2.0, -3.0],
+                view_transform: View::transform(
+                    &[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+                    &[1.0, 2.0, 3.0],
+                ),
+            },
+        };
+
+        // Another camera of the same sensor
+        let camera_other = Camera {
+            camera_id: 4,
+            image: Image {
+                image_file_path: "images/00004.png".into(),
+                image_id: 4,
+                ..Default::default()
+            },
+            view: View {
+                view_id: 4,
+                ..camera.view.to_owned()
+            },
+            ..camera.to_owned()
+        };
+
+        SparseViewDataset {
+            cameras: [(3, camera), (4, camera_other)].into_iter().collect(),
+            points: vec![Point {
+                color_rgb: [1.0, 0.0, 0.2],
+                position: [0.5, 0.25, 0.125],
+            }],
+        }
+    }
+
+    #[test]
+    fn encode_colmap_text() {
+        use super::*;
+
+        let dataset = dataset();
+
+        let mut output = vec![];
+        dataset.encode_colmap_cameras_text(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("# Number of cameras: 1\n"), "{output}");
+        let output = output
+            .lines()
+            .last()
+            .unwrap()
+            .split(' ')
+            .collect::<Vec<_>>();
+        assert_eq!(output[..4], ["1", "PINHOLE", "8", "6"]);
+        let output = output[4..]
+            .iter()
+            .map(|v| v.parse::<f64>().unwrap())
+            .collect::<Vec<_>>();
+        [4.0, 3.0, 4.0, 3.0]
+            .iter()
+            .zip(output)
+            .for_each(|(target, output)| {
+                assert!((output - target).abs() < 1e-9, "{output} != {target}");
+            });
+
+        let mut output = vec![];
+        dataset.encode_colmap_images_text(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let output = output.lines().rev().nth(3).unwrap();
+        assert_eq!(output, "3 1 0 0 0 1 2 3 1 00003.png");
+
+        let mut output = vec![];
+        dataset.encode_colmap_points_text(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let output = output.lines().last().unwrap();
+        assert_eq!(output, "1 0.5 0.25 0.125 255 0 51 0");
+    }
+
+    #[test]
+    fn encode_cameras_json() {
+        use super::*;
+
+        let mut output = vec![];
+        dataset().encode_cameras_json(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with('['), "{output}");
+        assert!(output.trim_end().ends_with(']'), "{output}");
+        assert!(output.contains("\"img_name\": \"00003\""), "{output}");
+        assert!(output.contains("\"position\": [-1, -2, -3]"), "{output}");
+        assert!(output.contains("\"rotation\": [[1, 0, 0], [0, 1, 0], [0, 0, 1]]"));
+
+        // The non-finite values are null
+        let mut dataset = dataset();
+        dataset.cameras.get_mut(&3).unwrap().view.view_position[0] = f64::NAN;
+        dataset.cameras.get_mut(&3).unwrap().view.field_of_view_x = 0.0;
+
+        let mut output = vec![];
+        dataset.encode_cameras_json(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\"position\": [null, -2, -3]"), "{output}");
+        assert!(output.contains("\"fx\": null"), "{output}");
+    }
+
+    #[test]
+    fn encode_json_string() {
+        use super::*;
+
+        let target = "\"a\\\"b\\\\c\\u000a\"";
+        let output = encode_json_string("a\"b\\c\n");
+        assert_eq!(output, target);
+    }
+}
@@ -0,0 +1,190 @@
+//! Merging sparse view datasets.
+//!
+//! Large captures are often reconstructed in chunks, which can be merged
+//! into one dataset after aligning them by similarity transformations.
+
+pub use super::*;
+
+use std::collections::HashSet;
+
+/// Merge operations
+impl SparseViewDataset {
+    /// Merge the datasets after applying their similarity transformations.
+    ///
+    /// ## Details
+    ///
+    /// - The camera IDs (image IDs and view IDs) of each dataset are offset
+    ///   by the next ID after the previous datasets, so they never collide.
+    /// - The sensor IDs are offset in the same way, since the sensors of
+    ///   different reconstructions are not known to be the same.
+    /// - The points are concatenated.
+    ///
+    /// It fails with [`Error::DuplicateImageFileName`] if an image file name
+    /// appears in more than one camera, or with [`Error::OverflowedId`]
+    /// if an offset ID exceeds `u32::MAX`.
+    pub fn merge<I: IntoIterator<Item = (Self, SimilarityTransform)>>(
+        datasets: I
+    ) -> Result<Self, Error> {
+        let mut merged = Self {
+            cameras: Default::default(),
+            points: Default::default(),
+        };
+        let mut image_file_names = HashSet::new();
+        let mut camera_id_offset = 0;
+        let mut sensor_id_offset = 0;
+
+        for (mut dataset, transform) in datasets {
+            dataset.transform(&transform);
+
+            let mut camera_id_next = camera_id_offset;
+            let mut sensor_id_next = sensor_id_offset;
+
+            for mut camera in dataset.cameras.into_values() {
+                let image_file_name = camera
+                    .image
+                    .image_file_path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_owned();
+                if !image_file_names.insert(image_file_name) {
+                    return Err(Error::DuplicateImageFileName(
+                        camera.image.image_file_path,
+                    ));
+                }
+
+                camera.camera_id = add_id(camera.camera_id, camera_id_offset)?;
+                camera.image.image_id = add_id(camera.image.image_id, camera_id_offset)?;
+                camera.view.view_id = add_id(camera.view.view_id, camera_id_offset)?;
+                camera.sensor_id = add_id(camera.sensor_id, sensor_id_offset)?;
+                camera_id_next = camera_id_next.max(add_id(camera.camera_id, 1)?);
+                sensor_id_next = sensor_id_next.max(add_id(camera.sensor_id, 1)?);
+
+                merged.cameras.insert(camera.camera_id, camera);
+            }
+
+            merged.points.extend(dataset.points);
+            camera_id_offset = camera_id_next;
+            sensor_id_offset = sensor_id_next;
+        }
+
+        #[cfg(all(debug_assertions, not(test)))]
+        log::debug!(
+            target: "gausplat::trainer::dataset::sparse_view",
+            "SparseViewDataset::merge > {merged:?}",
+        );
+
+        Ok(merged)
+    }
+}
+
+#[inline]
+fn add_id(
+    id: u32,
+    offset: u32,
+) -> Result<u32, Error> {
+    id.checked_add(offset)
+        .ok_or(Error::OverflowedId(id, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    fn dataset(names: &[&str]) -> super::SparseViewDataset {
+        use super::*;
+
+        super::SparseViewDataset {
+            cameras: names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| {
+                    let id = index as u32 + 1;
+                    let camera = Camera {
+                        camera_id: id,
+                        depth: None,
+                        image: Image {
+                            image_file_path: format!("images/{name}").into(),
+                            image_id: id,
+                            ..Default::default()
+                        },
+                        sensor_id: 1,
+                        view: View {
+                            view_id: id,
+                            view_position: [index as f64, 0.0, 0.0],
+                            view_transform: View::transform(
+                                &SimilarityTransform::IDENTITY.rotation,
+                                &[-(index as f64), 0.0, 0.0],
+                            ),
+                            ..Default::default()
+                        },
+                    };
+                    (id, camera)
+                })
+                .collect(),
+            points: vec![Point {
+                color_rgb: [0.5; 3],
+                position: [1.0, 2.0, 3.0],
+            }],
+        }
+    }
+
+    #[test]
+    fn merge() {
+        use super::*;
+
+        let transform = SimilarityTransform {
+            translation: [10.0, 0.0, 0.0],
+            ..SimilarityTransform::IDENTITY
+        };
+        let merged = SparseViewDataset::merge([
+            (dataset(&["a.png", "b.png"]), SimilarityTransform::IDENTITY),
+            (dataset(&["c.png"]), transform),
+        ])
+        .unwrap();
+
+        let target = vec![1, 2, 4];
+        let output = merged.cameras.keys().copied().collect::<Vec<_>>();
+        assert_eq!(output, target);
+
+        let camera = &merged.cameras[&4];
+        assert_eq!(camera.image.image_id, 4);
+        assert_eq!(camera.view.view_id, 4);
+        assert_eq!(camera.sensor_id, 3);
+        assert_eq!(camera.view.view_position, [10.0, 0.0, 0.0]);
+
+        let target = vec![[1.0, 2.0, 3.0], [11.0, 2.0, 3.0]];
+        let output = merged.points.iter().map(|p| p.position).collect::<Vec<_>>();
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn merge_with_duplicate_image_file_name() {
+        use super::*;
+
+        let error = SparseViewDataset::merge([
+            (dataset(&["a.png", "b.png"]), SimilarityTransform::IDENTITY),
+            (dataset(&["b.png"]), SimilarityTransform::IDENTITY),
+        ])
+        .unwrap_err();
+        assert!(
+            matches!(&error, Error::DuplicateImageFileName(path) if path.ends_with("b.png")),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn merge_with_overflowed_id() {
+        use super::*;
+
+        let mut dataset_last = dataset(&["b.png"]);
+        dataset_last.cameras.get_mut(&1).unwrap().camera_id = u32::MAX;
+
+        let error = SparseViewDataset::merge([
+            (dataset(&["a.png"]), SimilarityTransform::IDENTITY),
+            (dataset_last, SimilarityTransform::IDENTITY),
+        ])
+        .unwrap_err();
+        assert!(
+            matches!(error, Error::OverflowedId(u32::MAX, 2)),
+            "{error:?}"
+        );
+    }
+}
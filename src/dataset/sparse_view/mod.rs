@@ -1,14 +1,30 @@
 //! Sparse view dataset module.
 
+pub mod cache;
 pub mod camera;
+pub mod export;
+pub mod merge;
+pub mod points;
+pub mod sensor;
+pub mod synthesize;
+pub mod transform;
+pub mod validate;
 
 pub use crate::error::Error;
+pub use cache::*;
 pub use camera::*;
+pub use export::*;
 pub use gausplat_loader::source::colmap::{self, ColmapSource};
 pub use gausplat_renderer::scene::point::*;
+pub use merge::*;
+pub use points::*;
+pub use sensor::*;
+pub use synthesize::*;
+pub use transform::*;
+pub use validate::*;
 
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use std::{ffi::OsStr, fmt, io::Read, ops::Mul};
+use std::{ffi::OsStr, fmt, io::Read, ops::Mul, path::PathBuf};
 
 /// Dataset for sparse view.
 #[derive(Clone, PartialEq)]
@@ -21,98 +37,137 @@ pub struct SparseViewDataset {
 
 impl SparseViewDataset {
     /// Initialize from a COLMAP sparse reconstruction.
+    ///
+    /// It fails on the first invalid camera.
+    /// See [`SparseViewDataset::init_from_colmap_skipping_invalid`] for
+    /// a non-failing alternative.
+    #[inline]
     pub fn init_from_colmap<S: Read + Send + Sync>(
         source: ColmapSource<S>
     ) -> Result<Self, Error> {
+        Ok(Self::init_from_colmap_with(source, true)?.0)
+    }
+
+    /// Initialize from the valid subset of cameras in a COLMAP sparse reconstruction.
+    ///
+    /// ## Returns
+    ///
+    /// The dataset and the report of every problem found in the source.
+    #[inline]
+    pub fn init_from_colmap_skipping_invalid<S: Read + Send + Sync>(
+        source: ColmapSource<S>
+    ) -> Result<(Self, ValidationReport), Error> {
+        Self::init_from_colmap_with(source, false)
+    }
+
+    /// Validate a COLMAP sparse reconstruction.
+    ///
+    /// ## Returns
+    ///
+    /// The report of every problem found in the source.
+    #[inline]
+    pub fn validate_colmap<S: Read + Send + Sync>(
+        source: ColmapSource<S>
+    ) -> Result<ValidationReport, Error> {
+        Ok(Self::init_from_colmap_with(source, false)?.1)
+    }
+
+    fn init_from_colmap_with<S: Read + Send + Sync>(
+        source: ColmapSource<S>,
+        is_strict: bool,
+    ) -> Result<(Self, ValidationReport), Error> {
+        let mut report = ValidationReport {
+            image_count: source.images.len(),
+            ..Default::default()
+        };
         let points = source.points.into_iter().map(Into::into).collect();
 
-        let images_file = source
-            .images_file
-            .into_par_iter()
-            .map(|(image_file_path, image_file)| {
-                if image_file_path != image_file.path {
-                    return Err(Error::MismatchedImageFilePath(
-                        image_file_path,
-                        image_file.path,
-                    ));
-                }
+        let images_file =
+            source
+                .images_file
+                .into_par_iter()
+                .map(|(image_file_path, image_file)| {
+                    if image_file_path != image_file.path {
+                        return Err(Error::MismatchedImageFilePath(
+                            image_file_path,
+                            image_file.path,
+                        ));
+                    }
+
+                    let image_file_name = image_file_path
+                        .file_name()
+                        .filter(|_| image_file_path.is_file())
+                        .ok_or_else(|| Error::IoIsADirectory(image_file_path.to_owned()))?
+                        .to_owned();
 
-                let image_file_name = image_file_path
-                    .file_name()
-                    .filter(|_| image_file_path.is_file())
-                    .ok_or_else(|| Error::IoIsADirectory(image_file_path.to_owned()))?
-                    .to_owned();
-
-                Ok((image_file_name, image_file))
-            })
-            .collect::<Result<dashmap::DashMap<_, _>, Error>>()?;
-
-        let cameras = source
-            .images
-            .into_par_iter()
-            .map(|(id, image)| {
-                // Checking the image id
-                if id != image.image_id {
-                    return Err(Error::MismatchedImageId(id, image.image_id));
+                    Ok((image_file_name, image_file))
+                });
+        let images_file = if is_strict {
+            images_file
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .collect::<dashmap::DashMap<_, _>>()
+        } else {
+            let images_file_valid = dashmap::DashMap::new();
+            for result in images_file.collect::<Vec<_>>() {
+                match result {
+                    Ok((image_file_name, image_file)) => {
+                        images_file_valid.insert(image_file_name, image_file);
+                    },
+                    Err(error) => {
+                        report.push(None, &error);
+                    },
                 }
+            }
+            images_file_valid
+        };
 
-                // Specifying the parameters
-                let camera_id = image.camera_id;
-                let camera = source
-                    .cameras
-                    .get(&camera_id)
-                    .ok_or(Error::UnknownCameraId(camera_id))?;
-                let field_of_view_x = (camera.width as f64)
-                    .atan2(2.0 * camera.focal_length_x())
-                    .mul(2.0);
-                let field_of_view_y = (camera.height as f64)
-                    .atan2(2.0 * camera.focal_length_y())
-                    .mul(2.0);
-                // NOTE: Generally, the file name encoding is UTF-8 in COLMAP model.
-                let image_file_name =
-                    OsStr::new(image.file_name.to_str().map_err(|_| {
-                        Error::InvalidUtf8(image.file_name.to_string_lossy().into())
-                    })?);
+        let cameras = source.images.into_par_iter().map(|(id, image)| {
+            let camera = init_camera(id, image, &source.cameras, |image_file_name| {
                 let mut image_file = images_file
                     .remove(image_file_name)
                     .map(|p| p.1)
                     .ok_or_else(|| Error::UnknownImageFileName(image_file_name.into()))?;
                 // NOTE: Reading the image file at this point is more memory efficient.
-                let image_encoded = image_file.read_all()?;
-                let image_file_path = image_file.path;
-                let view_rotation = &image.rotation();
-                let view_position = image.position(view_rotation);
-                let view_transform = View::transform(view_rotation, &image.translation);
-
-                // Image
-                let image = Image {
-                    image_encoded,
-                    image_file_path,
-                    image_id: id,
-                };
-                let (image_width, image_height) = image.decode_dimensions()?;
-
-                // View
-                let view = View {
-                    field_of_view_x,
-                    field_of_view_y,
-                    image_height,
-                    image_width,
-                    view_id: id,
-                    view_position,
-                    view_transform,
-                };
-
-                // Camera
-                let camera = Camera {
-                    camera_id: id,
-                    image,
-                    view,
-                };
-
-                Ok((id, camera))
-            })
-            .collect::<Result<_, Error>>()?;
+                Ok((image_file.read_all()?, image_file.path))
+            });
+            (id, camera)
+        });
+        let cameras = if is_strict {
+            cameras
+                .map(|(id, camera)| Ok((id, Ok(camera?))))
+                .collect::<Result<Vec<_>, Error>>()?
+        } else {
+            cameras.collect::<Vec<_>>()
+        };
+
+        let mut cameras_valid = Cameras::default();
+        for (id, camera) in cameras {
+            match camera {
+                Ok((camera, camera_dimensions)) => {
+                    if !is_strict {
+                        report.check_camera(&camera, camera_dimensions);
+                    }
+                    cameras_valid.insert(id, camera);
+                },
+                Err(error) => {
+                    report.push(Some(id), &error);
+                },
+            }
+        }
+
+        let mut dataset = Self {
+            cameras: cameras_valid,
+            points,
+        };
+
+        if !is_strict {
+            report.check_dataset(&dataset);
+            let image_ids_invalid = report.image_ids_invalid();
+            dataset
+                .cameras
+                .retain(|id, _| !image_ids_invalid.contains(id));
+        }
 
         #[cfg(all(debug_assertions, not(test)))]
         log::debug!(
@@ -120,10 +175,84 @@ impl SparseViewDataset {
             "SparseViewDataset::init_from_colmap",
         );
 
-        Ok(Self { cameras, points })
+        Ok((dataset, report))
     }
 }
 
+/// Initialize the camera from the COLMAP image of the key `id`.
+///
+/// `read_image_file` reads the image file of the name,
+/// returning the encoded image and the file path.
+///
+/// ## Returns
+///
+/// The camera and the dimensions `[width, height]` of the COLMAP camera.
+fn init_camera(
+    id: u32,
+    image: colmap::Image,
+    cameras: &colmap::Cameras,
+    read_image_file: impl FnOnce(&OsStr) -> Result<(Vec<u8>, PathBuf), Error>,
+) -> Result<(Camera, [u32; 2]), Error> {
+    // Checking the image id
+    if id != image.image_id {
+        return Err(Error::MismatchedImageId(id, image.image_id));
+    }
+
+    // Specifying the parameters
+    let camera_id = image.camera_id;
+    let camera = cameras
+        .get(&camera_id)
+        .ok_or(Error::UnknownCameraId(camera_id))?;
+    let camera_dimensions = [camera.width as u32, camera.height as u32];
+    let field_of_view_x = (camera.width as f64)
+        .atan2(2.0 * camera.focal_length_x())
+        .mul(2.0);
+    let field_of_view_y = (camera.height as f64)
+        .atan2(2.0 * camera.focal_length_y())
+        .mul(2.0);
+    // NOTE: Generally, the file name encoding is UTF-8 in COLMAP model.
+    let image_file_name = OsStr::new(
+        image
+            .file_name
+            .to_str()
+            .map_err(|_| Error::InvalidUtf8(image.file_name.to_string_lossy().into()))?,
+    );
+    let (image_encoded, image_file_path) = read_image_file(image_file_name)?;
+    let view_rotation = &image.rotation();
+    let view_position = image.position(view_rotation);
+    let view_transform = View::transform(view_rotation, &image.translation);
+
+    // Image
+    let image = Image {
+        image_encoded,
+        image_file_path,
+        image_id: id,
+    };
+    let (image_width, image_height) = image.decode_dimensions()?;
+
+    // View
+    let view = View {
+        field_of_view_x,
+        field_of_view_y,
+        image_height,
+        image_width,
+        view_id: id,
+        view_position,
+        view_transform,
+    };
+
+    // Camera
+    let camera = Camera {
+        camera_id: id,
+        depth: None,
+        image,
+        sensor_id: camera_id,
+        view,
+    };
+
+    Ok((camera, camera_dimensions))
+}
+
 impl fmt::Debug for SparseViewDataset {
     fn fmt(
         &self,
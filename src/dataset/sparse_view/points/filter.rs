@@ -0,0 +1,418 @@
+//! Points filtering (cleaning) before the scene initialization.
+
+pub use super::*;
+
+use burn::config::Config;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+/// Configuration for filtering the points.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub struct PointsFilterConfig {
+    /// Far depth of the view frustums.
+    #[config(default = "f64::MAX")]
+    pub depth_far: f64,
+    /// Near depth of the view frustums.
+    #[config(default = "0.01")]
+    pub depth_near: f64,
+    /// Max ratio of the standard deviation for the statistical outlier removal.
+    ///
+    /// The points whose mean distance to their neighbors is greater than
+    /// `mean + deviation_ratio_max * std` are removed.
+    #[config(default = "2.0")]
+    pub deviation_ratio_max: f64,
+    /// Count of nearest neighbors for the statistical outlier removal.
+    ///
+    /// `0` disables the statistical outlier removal.
+    #[config(default = "20")]
+    pub neighbor_count: usize,
+    /// Min count of views whose frustum contains the point.
+    ///
+    /// `0` disables the visibility filtering.
+    /// It is also skipped if there are no cameras.
+    #[config(default = "1")]
+    pub view_count_min: usize,
+}
+
+/// Report of filtering the points.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PointsFilterReport {
+    /// Count of points before filtering.
+    pub point_count: usize,
+    /// Count of points removed since they are invisible in the views.
+    pub point_count_invisible: usize,
+    /// Count of points removed since they are statistical outliers.
+    pub point_count_outlier: usize,
+}
+
+impl PointsFilterReport {
+    /// Return the count of points removed.
+    #[inline]
+    pub fn point_count_removed(&self) -> usize {
+        self.point_count_invisible + self.point_count_outlier
+    }
+}
+
+/// Filtering operations
+impl SparseViewDataset {
+    /// Filter the points using the statistical outlier removal and
+    /// the visibility filtering.
+    ///
+    /// It should run before the scene initialization.
+    pub fn filter_points(
+        &mut self,
+        config: &PointsFilterConfig,
+    ) -> PointsFilterReport {
+        let point_count = self.points.len();
+        let point_count_invisible = self.remove_points_invisible(
+            config.view_count_min,
+            config.depth_near,
+            config.depth_far,
+        );
+        let point_count_outlier =
+            self.remove_points_outlier(config.neighbor_count, config.deviation_ratio_max);
+
+        let report = PointsFilterReport {
+            point_count,
+            point_count_invisible,
+            point_count_outlier,
+        };
+
+        #[cfg(all(debug_assertions, not(test)))]
+        log::debug!(
+            target: "gausplat::trainer::dataset::sparse_view",
+            "SparseViewDataset::filter_points > {report:?}",
+        );
+
+        report
+    }
+
+    /// Remove the points which are in fewer than `view_count_min` view frustums.
+    ///
+    /// Nothing is removed if there are no cameras.
+    ///
+    /// ## Returns
+    ///
+    /// The count of points removed.
+    pub fn remove_points_invisible(
+        &mut self,
+        view_count_min: usize,
+        depth_near: f64,
+        depth_far: f64,
+    ) -> usize {
+        if view_count_min == 0 || self.cameras.is_empty() {
+            return 0;
+        }
+
+        let is_visible = self
+            .points
+            .par_iter()
+            .map(|point| {
+                self.cameras
+                    .values()
+                    .filter(|camera| {
+                        camera.is_in_frustum(&point.position, depth_near, depth_far)
+                    })
+                    .take(view_count_min)
+                    .count()
+                    == view_count_min
+            })
+            .collect::<Vec<_>>();
+
+        self.retain_points(&is_visible)
+    }
+
+    /// Remove the statistical outliers of points.
+    ///
+    /// A point is an outlier if the mean distance to its `neighbor_count`
+    /// nearest neighbors is greater than `mean + deviation_ratio_max * std`
+    /// of all the mean distances.
+    ///
+    /// ## Returns
+    ///
+    /// The count of points removed.
+    pub fn remove_points_outlier(
+        &mut self,
+        neighbor_count: usize,
+        deviation_ratio_max: f64,
+    ) -> usize {
+        let point_count = self.points.len();
+        if neighbor_count == 0 || point_count <= neighbor_count {
+            return 0;
+        }
+
+        let positions = self.points.iter().map(|p| p.position).collect::<Vec<_>>();
+        let tree = KdTree::new(&positions);
+        let distances_mean = positions
+            .par_iter()
+            .map(|position| {
+                // NOTE: The nearest neighbor is the point itself.
+                let distances = tree.nearest(position, neighbor_count + 1);
+                distances.iter().skip(1).sum::<f64>() / neighbor_count as f64
+            })
+            .collect::<Vec<_>>();
+
+        let mean = distances_mean.iter().sum::<f64>() / point_count as f64;
+        let std = (distances_mean
+            .iter()
+            .map(|d| (d - mean).powi(2))
+            .sum::<f64>()
+            / point_count as f64)
+            .sqrt();
+        let threshold = mean + deviation_ratio_max * std;
+        let is_inlier = distances_mean
+            .into_iter()
+            .map(|d| d <= threshold)
+            .collect::<Vec<_>>();
+
+        self.retain_points(&is_inlier)
+    }
+
+    /// Retain the points by the mask.
+    ///
+    /// ## Returns
+    ///
+    /// The count of points removed.
+    fn retain_points(
+        &mut self,
+        mask: &[bool],
+    ) -> usize {
+        let point_count = self.points.len();
+        let mut mask = mask.iter();
+        self.points.retain(|_| *mask.next().unwrap_or(&true));
+        point_count - self.points.len()
+    }
+}
+
+/// A k-d tree for the nearest neighbor search.
+#[derive(Clone, Debug)]
+struct KdTree<'p> {
+    indices: Vec<usize>,
+    positions: &'p [[f64; 3]],
+}
+
+/// A distance ordered by value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Distance(f64);
+
+impl<'p> KdTree<'p> {
+    /// Build the tree in place by recursive median partitioning.
+    fn new(positions: &'p [[f64; 3]]) -> Self {
+        fn build(
+            indices: &mut [usize],
+            positions: &[[f64; 3]],
+            axis: usize,
+        ) {
+            if indices.len() <= 1 {
+                return;
+            }
+            let median = indices.len() / 2;
+            indices.select_nth_unstable_by(median, |a, b| {
+                positions[*a][axis].total_cmp(&positions[*b][axis])
+            });
+            let (left, right) = indices.split_at_mut(median);
+            build(left, positions, (axis + 1) % 3);
+            build(&mut right[1..], positions, (axis + 1) % 3);
+        }
+
+        let mut indices = (0..positions.len()).collect::<Vec<_>>();
+        build(&mut indices, positions, 0);
+
+        Self { indices, positions }
+    }
+
+    /// Return the ascending distances to the `count` nearest positions.
+    fn nearest(
+        &self,
+        target: &[f64; 3],
+        count: usize,
+    ) -> Vec<f64> {
+        fn search(
+            tree: &KdTree,
+            indices: &[usize],
+            target: &[f64; 3],
+            axis: usize,
+            count: usize,
+            heap: &mut BinaryHeap<Distance>,
+        ) {
+            if indices.is_empty() {
+                return;
+            }
+            let median = indices.len() / 2;
+            let position = &tree.positions[indices[median]];
+            let distance = (0..3)
+                .map(|i| (position[i] - target[i]).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            if heap.len() < count {
+                heap.push(Distance(distance));
+            } else if heap.peek().is_some_and(|d| distance < d.0) {
+                heap.pop();
+                heap.push(Distance(distance));
+            }
+
+            let offset = target[axis] - position[axis];
+            let (near, far) = if offset < 0.0 {
+                (&indices[..median], &indices[median + 1..])
+            } else {
+                (&indices[median + 1..], &indices[..median])
+            };
+            let axis_next = (axis + 1) % 3;
+            search(tree, near, target, axis_next, count, heap);
+            if heap.len() < count || heap.peek().is_some_and(|d| offset.abs() < d.0) {
+                search(tree, far, target, axis_next, count, heap);
+            }
+        }
+
+        let mut heap = BinaryHeap::with_capacity(count + 1);
+        search(self, &self.indices, target, 0, count, &mut heap);
+
+        heap.into_sorted_vec().into_iter().map(|d| d.0).collect()
+    }
+}
+
+impl Eq for Distance {}
+
+impl Ord for Distance {
+    #[inline]
+    fn cmp(
+        &self,
+        other: &Self,
+    ) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Distance {
+    #[inline]
+    fn partial_cmp(
+        &self,
+        other: &Self,
+    ) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Default for PointsFilterConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn kd_tree_nearest() {
+        use super::*;
+
+        let positions = (0..125)
+            .map(|i| [(i % 5) as f64, (i / 5 % 5) as f64, (i / 25) as f64])
+            .collect::<Vec<_>>();
+        let tree = KdTree::new(&positions);
+
+        positions.iter().step_by(7).for_each(|target| {
+            let mut distances = positions
+                .iter()
+                .map(|p| {
+                    (0..3)
+                        .map(|i| (p[i] - target[i]).powi(2))
+                        .sum::<f64>()
+                        .sqrt()
+                })
+                .collect::<Vec<_>>();
+            distances.sort_by(f64::total_cmp);
+            distances.truncate(8);
+
+            let output = tree.nearest(target, 8);
+            assert_eq!(output, distances, "{target:?}");
+        });
+    }
+
+    #[test]
+    fn remove_points_outlier() {
+        use super::*;
+
+        let mut dataset = SparseViewDataset {
+            points: (0..125)
+                .map(|i| Point {
+                    color_rgb: [0.5; 3],
+                    position: [(i % 5) as f64, (i / 5 % 5) as f64, (i / 25) as f64],
+                })
+                .collect(),
+            ..Default::default()
+        };
+        dataset.points.push(Point {
+            color_rgb: [0.5; 3],
+            position: [1e3, 1e3, 1e3],
+        });
+
+        let target = 1;
+        let output = dataset.remove_points_outlier(4, 2.0);
+        assert_eq!(output, target);
+        assert_eq!(dataset.points.len(), 125);
+        assert!(dataset.points.iter().all(|p| p.position[0] < 5.0));
+
+        let target = 0;
+        let output = dataset.remove_points_outlier(0, 2.0);
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn filter_points() {
+        use super::*;
+
+        let rotation = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let camera = Camera {
+            view: View {
+                field_of_view_x: 1.0,
+                field_of_view_y: 1.0,
+                image_height: 8,
+                image_width: 8,
+                view_id: 0,
+                view_position: [0.0, 0.0, 0.0],
+                view_transform: View::transform(&rotation, &[0.0, 0.0, 0.0]),
+            },
+            ..Default::default()
+        };
+        let mut dataset = SparseViewDataset {
+            cameras: [(0, camera)].into_iter().collect(),
+            points: vec![
+                Point {
+                    color_rgb: [0.5; 3],
+                    position: [0.0, 0.0, 1.0],
+                },
+                Point {
+                    color_rgb: [0.5; 3],
+                    position: [0.0, 0.0, -1.0],
+                },
+            ],
+        };
+
+        let target = PointsFilterReport {
+            point_count: 2,
+            point_count_invisible: 1,
+            point_count_outlier: 0,
+        };
+        let output = dataset.filter_points(&PointsFilterConfig::default());
+        assert_eq!(output, target);
+        assert_eq!(output.point_count_removed(), 1);
+        assert_eq!(dataset.points[0].position, [0.0, 0.0, 1.0]);
+
+        // Skipping the visibility filtering without cameras
+        dataset.cameras.clear();
+        dataset.points.push(Point {
+            color_rgb: [0.5; 3],
+            position: [0.0, 0.0, -1.0],
+        });
+
+        let target = PointsFilterReport {
+            point_count: 2,
+            point_count_invisible: 0,
+            point_count_outlier: 0,
+        };
+        let output = dataset.filter_points(&PointsFilterConfig::default());
+        assert_eq!(output, target);
+        assert_eq!(dataset.points.len(), 2);
+    }
+}
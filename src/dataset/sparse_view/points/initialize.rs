@@ -0,0 +1,374 @@
+//! Random points initialization.
+//!
+//! It is useful when no SfM points are available.
+
+pub use super::*;
+
+use burn::config::Config;
+use gausplat_renderer::scene::gaussian_3d::SEED;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::f64::consts::TAU;
+
+/// Configuration for random points initialization.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub struct PointsInitializerConfig {
+    /// Count of points to generate.
+    #[config(default = "100000")]
+    pub count: usize,
+    /// Far depth of the view frustums.
+    #[config(default = "100.0")]
+    pub depth_far: f64,
+    /// Near depth of the view frustums.
+    #[config(default = "0.01")]
+    pub depth_near: f64,
+    /// Max count of sampling attempts for each point.
+    ///
+    /// It bounds the rejection sampling in the frustum intersection.
+    #[config(default = "64")]
+    pub sample_count_max: usize,
+    /// Seed for the random number generator.
+    #[config(default = "SEED")]
+    pub seed: u64,
+}
+
+impl PointsInitializerConfig {
+    /// Generate the points uniformly inside the bounding box.
+    pub fn init_in_box(
+        &self,
+        bounds: &BoundingBox,
+    ) -> Points {
+        let rng = &mut StdRng::seed_from_u64(self.seed);
+
+        (0..self.count)
+            .map(|_| Point {
+                color_rgb: Self::sample_color_rgb(rng),
+                position: Self::sample_position_in_box(rng, bounds),
+            })
+            .collect()
+    }
+
+    /// Generate the points uniformly inside the intersection of
+    /// all the view frustums.
+    ///
+    /// ## Details
+    ///
+    /// The points are rejection sampled inside the intersection of
+    /// the frustum bounding boxes, so fewer points than
+    /// [`PointsInitializerConfig::count`] may be generated.
+    /// No points are generated if the intersection is empty.
+    pub fn init_in_frustums(
+        &self,
+        cameras: &Cameras,
+    ) -> Points {
+        let rng = &mut StdRng::seed_from_u64(self.seed);
+
+        let bounds = cameras.values().try_fold(None, |bounds, camera| {
+            let corners = camera.frustum_corners(self.depth_near, self.depth_far);
+            let bounds_camera = BoundingBox::from_positions(&corners)?;
+            match bounds {
+                None => Some(Some(bounds_camera)),
+                Some(bounds) => bounds_camera.intersect(&bounds).map(Some),
+            }
+        });
+        let Some(Some(bounds)) = bounds else {
+            return Default::default();
+        };
+
+        let is_in_frustums = |position: &[f64; 3]| {
+            cameras.values().all(|camera| {
+                camera.is_in_frustum(position, self.depth_near, self.depth_far)
+            })
+        };
+
+        // NOTE: The attempts are unbounded on overflow, bounded by `take` instead.
+        let sample_count = self
+            .count
+            .checked_mul(self.sample_count_max)
+            .unwrap_or(usize::MAX);
+        (0..sample_count)
+            .map(|_| Self::sample_position_in_box(rng, &bounds))
+            .filter(is_in_frustums)
+            .take(self.count)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|position| Point {
+                color_rgb: Self::sample_color_rgb(rng),
+                position,
+            })
+            .collect()
+    }
+
+    /// Generate the points uniformly inside the spherical shell.
+    ///
+    /// It is useful to represent the background of unbounded scenes.
+    ///
+    /// It fails with [`Error::InvalidRadii`] unless
+    /// `0 <= radius_inner <= radius_outer` and both are finite.
+    pub fn init_in_sphere_shell(
+        &self,
+        center: &[f64; 3],
+        radius_inner: f64,
+        radius_outer: f64,
+    ) -> Result<Points, Error> {
+        if !(radius_inner >= 0.0
+            && radius_inner <= radius_outer
+            && radius_outer.is_finite())
+        {
+            return Err(Error::InvalidRadii(radius_inner, radius_outer));
+        }
+
+        let rng = &mut StdRng::seed_from_u64(self.seed);
+        let radius_inner3 = radius_inner.powi(3);
+        let radius_outer3 = radius_outer.powi(3);
+
+        Ok((0..self.count)
+            .map(|_| {
+                // Sampling the radius uniformly in volume
+                let radius = rng.gen_range(radius_inner3..=radius_outer3).cbrt();
+                // Sampling the direction uniformly on the unit sphere
+                let z = rng.gen_range(-1.0..=1.0_f64);
+                let phi = rng.gen_range(0.0..TAU);
+                let rho = (1.0 - z * z).max(0.0).sqrt();
+                let direction = [rho * phi.cos(), rho * phi.sin(), z];
+
+                Point {
+                    color_rgb: Self::sample_color_rgb(rng),
+                    position: [0, 1, 2].map(|i| center[i] + radius * direction[i]),
+                }
+            })
+            .collect())
+    }
+
+    fn sample_color_rgb(rng: &mut StdRng) -> [f64; 3] {
+        [(); 3].map(|_| rng.gen_range(0.0..=1.0))
+    }
+
+    fn sample_position_in_box(
+        rng: &mut StdRng,
+        bounds: &BoundingBox,
+    ) -> [f64; 3] {
+        [0, 1, 2].map(|i| rng.gen_range(bounds.min[i]..=bounds.max[i]))
+    }
+}
+
+/// Initialization operations
+impl SparseViewDataset {
+    /// Replace the points with the random points inside the bounding box
+    /// of the cameras, scaled by `factor`.
+    ///
+    /// It fails with [`Error::InvalidScaleFactor`] unless
+    /// `factor` is finite and non-negative.
+    pub fn init_points_in_cameras_box(
+        &mut self,
+        config: &PointsInitializerConfig,
+        factor: f64,
+    ) -> Result<&mut Self, Error> {
+        if !(factor >= 0.0 && factor.is_finite()) {
+            return Err(Error::InvalidScaleFactor(factor));
+        }
+
+        self.points = self
+            .bounding_box_of_cameras()
+            .map(|bounds| config.init_in_box(&bounds.scale(factor)))
+            .unwrap_or_default();
+        Ok(self)
+    }
+
+    /// Replace the points with the random points inside the intersection of
+    /// all the view frustums.
+    pub fn init_points_in_frustums(
+        &mut self,
+        config: &PointsInitializerConfig,
+    ) -> &mut Self {
+        self.points = config.init_in_frustums(&self.cameras);
+        self
+    }
+
+    /// Append the random points inside the spherical shell around the cameras.
+    ///
+    /// The shell is centered at the center of cameras and its radii are
+    /// `factor_inner` and `factor_outer` times the radius of cameras.
+    ///
+    /// See [`PointsInitializerConfig::init_in_sphere_shell`] for the errors.
+    pub fn extend_points_in_sphere_shell(
+        &mut self,
+        config: &PointsInitializerConfig,
+        factor_inner: f64,
+        factor_outer: f64,
+    ) -> Result<&mut Self, Error> {
+        let Some(bounds) = self.bounding_box_of_cameras() else {
+            return Ok(self);
+        };
+        let center = bounds.center();
+        let radius = self
+            .cameras
+            .values()
+            .map(|camera| {
+                let p = &camera.view.view_position;
+                (0..3)
+                    .map(|i| (p[i] - center[i]).powi(2))
+                    .sum::<f64>()
+                    .sqrt()
+            })
+            .fold(0.0, f64::max);
+
+        self.points.extend(config.init_in_sphere_shell(
+            &center,
+            radius * factor_inner,
+            radius * factor_outer,
+        )?);
+        Ok(self)
+    }
+}
+
+impl Default for PointsInitializerConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn init_in_box() {
+        use super::*;
+
+        let bounds = BoundingBox {
+            max: [1.0, 2.0, 3.0],
+            min: [-1.0, 0.0, 2.0],
+        };
+        let config = PointsInitializerConfig::default().with_count(1000);
+        let points = config.init_in_box(&bounds);
+
+        assert_eq!(points.len(), 1000);
+        points.iter().for_each(|point| {
+            (0..3).for_each(|i| {
+                assert!(point.position[i] >= bounds.min[i], "{point:?}");
+                assert!(point.position[i] <= bounds.max[i], "{point:?}");
+                assert!((0.0..=1.0).contains(&point.color_rgb[i]), "{point:?}");
+            });
+        });
+
+        let target = points;
+        let output = config.init_in_box(&bounds);
+        assert_eq!(output, target);
+
+        let output = config.with_seed(config.seed + 1).init_in_box(&bounds);
+        assert_ne!(output, target);
+    }
+
+    #[test]
+    fn init_points_in_cameras_box() {
+        use super::*;
+
+        let mut dataset = SparseViewDataset {
+            cameras: Default::default(),
+            points: vec![Point {
+                color_rgb: [0.5; 3],
+                position: [0.0; 3],
+            }],
+        };
+        let config = PointsInitializerConfig::default().with_count(10);
+
+        let target = 0;
+        let output = dataset
+            .init_points_in_cameras_box(&config, 1.5)
+            .unwrap()
+            .points
+            .len();
+        assert_eq!(output, target);
+
+        for factor in [-1.0, f64::NAN, f64::INFINITY] {
+            let error = dataset.init_points_in_cameras_box(&config, factor);
+            assert!(
+                matches!(error, Err(Error::InvalidScaleFactor(_))),
+                "{factor}"
+            );
+        }
+    }
+
+    #[test]
+    fn init_in_frustums() {
+        use super::*;
+
+        let rotation = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let camera_0 = Camera {
+            view: View {
+                field_of_view_x: 1.0,
+                field_of_view_y: 1.0,
+                image_height: 8,
+                image_width: 8,
+                view_id: 0,
+                view_position: [0.0, 0.0, 0.0],
+                view_transform: View::transform(&rotation, &[0.0, 0.0, 0.0]),
+            },
+            ..Default::default()
+        };
+        let mut camera_1 = camera_0.to_owned();
+        camera_1.camera_id = 1;
+        camera_1.view.view_id = 1;
+        camera_1.view.view_position = [0.5, 0.0, 0.0];
+        camera_1.view.view_transform = View::transform(&rotation, &[-0.5, 0.0, 0.0]);
+        let cameras = [(0, camera_0), (1, camera_1)]
+            .into_iter()
+            .collect::<Cameras>();
+
+        let config = PointsInitializerConfig::default()
+            .with_count(100)
+            .with_depth_far(10.0);
+        let points = config.init_in_frustums(&cameras);
+
+        assert_eq!(points.len(), 100);
+        points.iter().for_each(|point| {
+            cameras.values().for_each(|camera| {
+                assert!(
+                    camera.is_in_frustum(&point.position, 0.01, 10.0),
+                    "{point:?}"
+                );
+            });
+        });
+
+        let target = 0;
+        let output = config.init_in_frustums(&Default::default()).len();
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn init_in_sphere_shell() {
+        use super::*;
+
+        let center = [1.0, 2.0, 3.0];
+        let config = PointsInitializerConfig::default().with_count(1000);
+        let points = config.init_in_sphere_shell(&center, 10.0, 20.0).unwrap();
+
+        assert_eq!(points.len(), 1000);
+        points.iter().for_each(|point| {
+            let radius = (0..3)
+                .map(|i| (point.position[i] - center[i]).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            assert!((10.0 - 1e-9..=20.0 + 1e-9).contains(&radius), "{point:?}");
+        });
+
+        let target = 1000;
+        let output = config
+            .init_in_sphere_shell(&center, 0.0, 0.0)
+            .unwrap()
+            .len();
+        assert_eq!(output, target);
+
+        config
+            .init_in_sphere_shell(&center, 20.0, 10.0)
+            .unwrap_err();
+        config
+            .init_in_sphere_shell(&center, -1.0, 10.0)
+            .unwrap_err();
+        config
+            .init_in_sphere_shell(&center, 1.0, f64::INFINITY)
+            .unwrap_err();
+        config
+            .init_in_sphere_shell(&center, f64::NAN, 10.0)
+            .unwrap_err();
+    }
+}
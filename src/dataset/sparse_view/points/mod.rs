@@ -0,0 +1,239 @@
+//! Points operations for sparse view.
+
+pub mod filter;
+pub mod initialize;
+pub mod ply;
+
+pub use super::*;
+pub use filter::*;
+pub use initialize::*;
+pub use ply::*;
+
+use std::collections::{BTreeMap, HashSet};
+
+/// An axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BoundingBox {
+    /// The maximum corner.
+    pub max: [f64; 3],
+    /// The minimum corner.
+    pub min: [f64; 3],
+}
+
+impl BoundingBox {
+    /// Initialize from the positions.
+    ///
+    /// ## Returns
+    ///
+    /// `None` if the positions are empty.
+    pub fn from_positions<'p, I: IntoIterator<Item = &'p [f64; 3]>>(
+        positions: I
+    ) -> Option<Self> {
+        positions.into_iter().fold(None, |bounds, position| {
+            let mut bounds = bounds.unwrap_or(Self {
+                max: *position,
+                min: *position,
+            });
+            (0..3).for_each(|i| {
+                bounds.max[i] = bounds.max[i].max(position[i]);
+                bounds.min[i] = bounds.min[i].min(position[i]);
+            });
+            Some(bounds)
+        })
+    }
+
+    /// Return the center.
+    #[inline]
+    pub fn center(&self) -> [f64; 3] {
+        [0, 1, 2].map(|i| (self.max[i] + self.min[i]) / 2.0)
+    }
+
+    /// Return the side lengths.
+    #[inline]
+    pub fn extent(&self) -> [f64; 3] {
+        [0, 1, 2].map(|i| self.max[i] - self.min[i])
+    }
+
+    /// Return the intersection with `other`.
+    ///
+    /// ## Returns
+    ///
+    /// `None` if the boxes are disjoint.
+    pub fn intersect(
+        &self,
+        other: &Self,
+    ) -> Option<Self> {
+        let max = [0, 1, 2].map(|i| self.max[i].min(other.max[i]));
+        let min = [0, 1, 2].map(|i| self.min[i].max(other.min[i]));
+        (0..3)
+            .all(|i| min[i] <= max[i])
+            .then_some(Self { max, min })
+    }
+
+    /// Scale the box around its center by `factor`.
+    pub fn scale(
+        mut self,
+        factor: f64,
+    ) -> Self {
+        let center = self.center();
+        (0..3).for_each(|i| {
+            self.max[i] = center[i] + (self.max[i] - center[i]) * factor;
+            self.min[i] = center[i] + (self.min[i] - center[i]) * factor;
+        });
+        self
+    }
+}
+
+/// Downsample the points by averaging the points in each voxel.
+///
+/// ## Details
+///
+/// The voxel size is the smallest one (found by bisection) which makes
+/// the count of occupied voxels not greater than `count_max`.
+pub fn downsample_points_by_voxel(
+    points: &[Point],
+    count_max: usize,
+) -> Points {
+    const ITERATION_COUNT: usize = 32;
+
+    if points.len() <= count_max {
+        return points.to_vec();
+    }
+    if count_max == 0 {
+        return Default::default();
+    }
+
+    let Some(bounds) = BoundingBox::from_positions(points.iter().map(|p| &p.position))
+    else {
+        return Default::default();
+    };
+    let voxel_of = |position: &[f64; 3], size: f64| {
+        [0, 1, 2].map(|i| ((position[i] - bounds.min[i]) / size).floor() as i64)
+    };
+
+    // Bisecting the voxel size
+    let mut size_max = bounds.extent().into_iter().fold(0.0, f64::max) * 2.0;
+    let mut size_min = 0.0;
+    if size_max == 0.0 {
+        return points[..1].to_vec();
+    }
+    for _ in 0..ITERATION_COUNT {
+        let size = (size_max + size_min) / 2.0;
+        let voxel_count = points
+            .iter()
+            .map(|p| voxel_of(&p.position, size))
+            .collect::<HashSet<_>>()
+            .len();
+        if voxel_count > count_max {
+            size_min = size;
+        } else {
+            size_max = size;
+        }
+    }
+
+    // Averaging the points in each voxel
+    let mut voxels = BTreeMap::<_, (Point, usize)>::new();
+    points.iter().for_each(|point| {
+        let (sum, count) = voxels
+            .entry(voxel_of(&point.position, size_max))
+            .or_default();
+        (0..3).for_each(|i| {
+            sum.color_rgb[i] += point.color_rgb[i];
+            sum.position[i] += point.position[i];
+        });
+        *count += 1;
+    });
+
+    voxels
+        .into_values()
+        .map(|(sum, count)| Point {
+            color_rgb: sum.color_rgb.map(|v| v / count as f64),
+            position: sum.position.map(|v| v / count as f64),
+        })
+        .collect()
+}
+
+/// Bounding operations
+impl SparseViewDataset {
+    /// Return the bounding box of the camera positions.
+    #[inline]
+    pub fn bounding_box_of_cameras(&self) -> Option<BoundingBox> {
+        BoundingBox::from_positions(self.cameras.values().map(|c| &c.view.view_position))
+    }
+
+    /// Return the bounding box of the points.
+    #[inline]
+    pub fn bounding_box_of_points(&self) -> Option<BoundingBox> {
+        BoundingBox::from_positions(self.points.iter().map(|p| &p.position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn downsample_points_by_voxel() {
+        use super::*;
+
+        let points = (0..1000)
+            .map(|i| Point {
+                color_rgb: [0.5; 3],
+                position: [(i % 10) as f64, (i / 10 % 10) as f64, (i / 100) as f64],
+            })
+            .collect::<Vec<_>>();
+
+        let target = points.to_owned();
+        let output = super::downsample_points_by_voxel(&points, 1000);
+        assert_eq!(output, target);
+
+        let output = super::downsample_points_by_voxel(&points, 100);
+        assert!(!output.is_empty());
+        assert!(output.len() <= 100, "{}", output.len());
+        output.iter().for_each(|point| {
+            assert_eq!(point.color_rgb, [0.5; 3]);
+        });
+
+        let target = 0;
+        let output = super::downsample_points_by_voxel(&points, 0).len();
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn bounding_box() {
+        use super::*;
+
+        let positions: [[f64; 3]; 0] = [];
+        let target = None;
+        let output = BoundingBox::from_positions(&positions);
+        assert_eq!(output, target);
+
+        let bounds =
+            BoundingBox::from_positions(&[[1.0, -2.0, 3.0], [-1.0, 2.0, 5.0]]).unwrap();
+        assert_eq!(bounds.max, [1.0, 2.0, 5.0]);
+        assert_eq!(bounds.min, [-1.0, -2.0, 3.0]);
+        assert_eq!(bounds.center(), [0.0, 0.0, 4.0]);
+        assert_eq!(bounds.extent(), [2.0, 4.0, 2.0]);
+
+        let bounds_scaled = bounds.scale(2.0);
+        assert_eq!(bounds_scaled.max, [2.0, 4.0, 6.0]);
+        assert_eq!(bounds_scaled.min, [-2.0, -4.0, 2.0]);
+
+        let other = BoundingBox {
+            max: [9.0, 9.0, 9.0],
+            min: [0.0, 0.0, 4.0],
+        };
+        let target = Some(BoundingBox {
+            max: [1.0, 2.0, 5.0],
+            min: [0.0, 0.0, 4.0],
+        });
+        let output = bounds.intersect(&other);
+        assert_eq!(output, target);
+
+        let other = BoundingBox {
+            max: [9.0, 9.0, 9.0],
+            min: [8.0, 8.0, 8.0],
+        };
+        let target = None;
+        let output = bounds.intersect(&other);
+        assert_eq!(output, target);
+    }
+}
@@ -0,0 +1,411 @@
+//! Points from the polygon file (PLY).
+//!
+//! Only the `vertex` element is read. The positions are taken from the
+//! properties `x`, `y` and `z`, and the colors from the optional properties
+//! `red`, `green` and `blue`.
+
+pub use super::*;
+
+use std::io::{BufRead, BufReader, Read};
+
+/// Format of the polygon file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PlyFormat {
+    Ascii,
+    BinaryBigEndian,
+    BinaryLittleEndian,
+}
+
+/// Scalar type of a property in the polygon file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PlyScalar {
+    F32,
+    F64,
+    I8,
+    I16,
+    I32,
+    U8,
+    U16,
+    U32,
+}
+
+/// Kind of a property in the polygon file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PlyProperty {
+    List(PlyScalar, PlyScalar),
+    Scalar(PlyScalar),
+}
+
+/// Element in the polygon file.
+#[derive(Clone, Debug, PartialEq)]
+struct PlyElement {
+    count: usize,
+    name: String,
+    properties: Vec<(String, PlyProperty)>,
+}
+
+/// Decode the points from the polygon file (PLY).
+///
+/// Both ASCII and binary formats are supported.
+/// The points without colors are gray.
+pub fn decode_points_ply<R: Read>(reader: R) -> Result<Points, Error> {
+    let reader = &mut BufReader::new(reader);
+    let (format, elements) = decode_ply_header(reader)?;
+
+    let mut points = Points::new();
+    for element in elements {
+        let is_vertex = element.name == "vertex";
+        let index_of = |name: &str| {
+            element
+                .properties
+                .iter()
+                .position(|(property_name, _)| property_name == name)
+        };
+        let indices_position = ["x", "y", "z"].map(index_of);
+        let indices_color = ["red", "green", "blue"].map(index_of);
+        if is_vertex && indices_position.contains(&None) {
+            return Err(Error::InvalidPly(
+                "the vertex element has no position properties".into(),
+            ));
+        }
+
+        let mut line = String::new();
+        for _ in 0..element.count {
+            let mut values = Vec::with_capacity(element.properties.len());
+
+            if format == PlyFormat::Ascii {
+                line.clear();
+                reader.read_line(&mut line)?;
+                let tokens = &mut line.split_whitespace();
+                for (_, property) in &element.properties {
+                    values.push(decode_ply_value_ascii(tokens, property)?);
+                }
+            } else {
+                for (_, property) in &element.properties {
+                    values.push(decode_ply_value_binary(reader, format, property)?);
+                }
+            }
+
+            if !is_vertex {
+                continue;
+            }
+
+            let position = indices_position.map(|i| values[i.unwrap_or_default()].0);
+            let color_rgb = indices_color.map(|i| match i {
+                Some(i) => values[i].0 / values[i].1,
+                None => 0.5,
+            });
+            points.push(Point {
+                color_rgb,
+                position,
+            });
+        }
+
+        // NOTE: The elements after the vertex element are not needed.
+        if is_vertex {
+            return Ok(points);
+        }
+    }
+
+    Err(Error::InvalidPly("the vertex element is missing".into()))
+}
+
+fn decode_ply_header<R: BufRead>(
+    reader: &mut R
+) -> Result<(PlyFormat, Vec<PlyElement>), Error> {
+    let mut elements = Vec::<PlyElement>::new();
+    let mut format = None;
+    let mut line = String::new();
+
+    reader.read_line(&mut line)?;
+    if line.trim_end() != "ply" {
+        return Err(Error::InvalidPly("the magic number is not `ply`".into()));
+    }
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::InvalidPly("the header is not ended".into()));
+        }
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+        match tokens.as_slice() {
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {},
+            ["format", kind, _] => {
+                format = Some(match *kind {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    _ => {
+                        return Err(Error::InvalidPly(format!("unknown format {kind:?}")))
+                    },
+                });
+            },
+            ["element", name, count] => {
+                let count = count.parse().map_err(|_| {
+                    Error::InvalidPly(format!("invalid element count {count:?}"))
+                })?;
+                elements.push(PlyElement {
+                    count,
+                    name: name.to_string(),
+                    properties: vec![],
+                });
+            },
+            ["property", "list", count_kind, value_kind, name] => {
+                let property = PlyProperty::List(
+                    decode_ply_scalar(count_kind)?,
+                    decode_ply_scalar(value_kind)?,
+                );
+                elements
+                    .last_mut()
+                    .ok_or_else(|| Error::InvalidPly("property without element".into()))?
+                    .properties
+                    .push((name.to_string(), property));
+            },
+            ["property", kind, name] => {
+                let property = PlyProperty::Scalar(decode_ply_scalar(kind)?);
+                elements
+                    .last_mut()
+                    .ok_or_else(|| Error::InvalidPly("property without element".into()))?
+                    .properties
+                    .push((name.to_string(), property));
+            },
+            _ => {
+                return Err(Error::InvalidPly(format!(
+                    "invalid header line {:?}",
+                    line.trim_end()
+                )))
+            },
+        }
+    }
+
+    let format =
+        format.ok_or_else(|| Error::InvalidPly("the format is missing".into()))?;
+
+    Ok((format, elements))
+}
+
+fn decode_ply_scalar(kind: &str) -> Result<PlyScalar, Error> {
+    Ok(match kind {
+        "char" | "int8" => PlyScalar::I8,
+        "uchar" | "uint8" => PlyScalar::U8,
+        "short" | "int16" => PlyScalar::I16,
+        "ushort" | "uint16" => PlyScalar::U16,
+        "int" | "int32" => PlyScalar::I32,
+        "uint" | "uint32" => PlyScalar::U32,
+        "float" | "float32" => PlyScalar::F32,
+        "double" | "float64" => PlyScalar::F64,
+        _ => return Err(Error::InvalidPly(format!("unknown property type {kind:?}"))),
+    })
+}
+
+impl PlyScalar {
+    /// Return the byte size.
+    #[inline]
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::F32 | Self::I32 | Self::U32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    /// Return the maximum value used for normalizing the colors.
+    #[inline]
+    fn value_max(self) -> f64 {
+        match self {
+            Self::F32 | Self::F64 => 1.0,
+            Self::I8 => i8::MAX as f64,
+            Self::I16 => i16::MAX as f64,
+            Self::I32 => i32::MAX as f64,
+            Self::U8 => u8::MAX as f64,
+            Self::U16 => u16::MAX as f64,
+            Self::U32 => u32::MAX as f64,
+        }
+    }
+}
+
+/// ## Returns
+///
+/// The value and its maximum value. The value of a list property is zero.
+fn decode_ply_value_ascii<'t, I: Iterator<Item = &'t str>>(
+    tokens: &mut I,
+    property: &PlyProperty,
+) -> Result<(f64, f64), Error> {
+    let mut next = || -> Result<f64, Error> {
+        let token = tokens
+            .next()
+            .ok_or_else(|| Error::InvalidPly("missing property value".into()))?;
+        token
+            .parse()
+            .map_err(|_| Error::InvalidPly(format!("invalid property value {token:?}")))
+    };
+
+    match property {
+        PlyProperty::Scalar(scalar) => Ok((next()?, scalar.value_max())),
+        PlyProperty::List(..) => {
+            let count = next()? as usize;
+            for _ in 0..count {
+                next()?;
+            }
+            Ok((0.0, 1.0))
+        },
+    }
+}
+
+/// ## Returns
+///
+/// The value and its maximum value. The value of a list property is zero.
+fn decode_ply_value_binary<R: Read>(
+    reader: &mut R,
+    format: PlyFormat,
+    property: &PlyProperty,
+) -> Result<(f64, f64), Error> {
+    let mut next = |scalar: PlyScalar| -> Result<f64, Error> {
+        let mut bytes = [0; 8];
+        let bytes = &mut bytes[..scalar.size()];
+        reader.read_exact(bytes)?;
+        if format == PlyFormat::BinaryBigEndian {
+            bytes.reverse();
+        }
+        let value = match scalar {
+            PlyScalar::F32 => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            PlyScalar::F64 => f64::from_le_bytes(bytes.try_into().unwrap()),
+            PlyScalar::I8 => i8::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            PlyScalar::I16 => i16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            PlyScalar::I32 => i32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            PlyScalar::U8 => u8::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            PlyScalar::U16 => u16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            PlyScalar::U32 => u32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        };
+        Ok(value)
+    };
+
+    match *property {
+        PlyProperty::Scalar(scalar) => Ok((next(scalar)?, scalar.value_max())),
+        PlyProperty::List(count_scalar, value_scalar) => {
+            let count = next(count_scalar)? as usize;
+            for _ in 0..count {
+                next(value_scalar)?;
+            }
+            Ok((0.0, 1.0))
+        },
+    }
+}
+
+/// PLY operations
+impl SparseViewDataset {
+    /// Replace the points with the ones decoded from the polygon file (PLY).
+    ///
+    /// If `count_max` is specified, the points are downsampled by voxels
+    /// to at most `count_max` points.
+    pub fn init_points_from_ply<R: Read>(
+        &mut self,
+        reader: R,
+        count_max: Option<usize>,
+    ) -> Result<&mut Self, Error> {
+        let points = decode_points_ply(reader)?;
+        self.points = match count_max {
+            Some(count_max) => downsample_points_by_voxel(&points, count_max),
+            None => points,
+        };
+
+        #[cfg(all(debug_assertions, not(test)))]
+        log::debug!(
+            target: "gausplat::trainer::dataset::sparse_view",
+            "SparseViewDataset::init_points_from_ply > point_count ({})",
+            self.points.len(),
+        );
+
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn decode_points_ply_ascii() {
+        use super::*;
+
+        let source = b"ply\n\
+            format ascii 1.0\n\
+            comment made by hand\n\
+            element vertex 2\n\
+            property float x\n\
+            property float y\n\
+            property float z\n\
+            property uchar red\n\
+            property uchar green\n\
+            property uchar blue\n\
+            element face 1\n\
+            property list uchar int vertex_indices\n\
+            end_header\n\
+            1 2 3 255 0 51\n\
+            -1 -2 -3 0 255 102\n\
+            3 0 1 1\n";
+
+        let target = vec![
+            Point {
+                color_rgb: [1.0, 0.0, 0.2],
+                position: [1.0, 2.0, 3.0],
+            },
+            Point {
+                color_rgb: [0.0, 1.0, 0.4],
+                position: [-1.0, -2.0, -3.0],
+            },
+        ];
+        let output = decode_points_ply(source.as_slice()).unwrap();
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn decode_points_ply_binary() {
+        use super::*;
+
+        let mut source = b"ply\n\
+            format binary_little_endian 1.0\n\
+            element vertex 2\n\
+            property double x\n\
+            property double y\n\
+            property double z\n\
+            property list uchar int extra\n\
+            end_header\n"
+            .to_vec();
+        for position in [[1.0_f64, 2.0, 3.0], [4.0, 5.0, 6.0]] {
+            position
+                .iter()
+                .for_each(|v| source.extend_from_slice(&v.to_le_bytes()));
+            source.push(1);
+            source.extend_from_slice(&7_i32.to_le_bytes());
+        }
+
+        let target = vec![
+            Point {
+                color_rgb: [0.5; 3],
+                position: [1.0, 2.0, 3.0],
+            },
+            Point {
+                color_rgb: [0.5; 3],
+                position: [4.0, 5.0, 6.0],
+            },
+        ];
+        let output = decode_points_ply(source.as_slice()).unwrap();
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn decode_points_ply_invalid() {
+        use super::*;
+
+        decode_points_ply(b"".as_slice()).unwrap_err();
+        decode_points_ply(b"ply\nformat ascii 1.0\n".as_slice()).unwrap_err();
+        decode_points_ply(b"ply\nformat ascii 1.0\nend_header\n".as_slice()).unwrap_err();
+        decode_points_ply(
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n1\n"
+                .as_slice(),
+        )
+        .unwrap_err();
+    }
+}
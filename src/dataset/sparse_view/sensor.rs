@@ -0,0 +1,115 @@
+//! Sensor grouping for sparse view datasets.
+//!
+//! The cameras captured by the same physical sensor share
+//! [`Camera::sensor_id`]. It is useful for per-sensor appearance compensation
+//! and rig-aware dataset splits.
+
+pub use super::*;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Sensor operations
+impl SparseViewDataset {
+    /// Return the sensor IDs in ascending order.
+    #[inline]
+    pub fn sensor_ids(&self) -> BTreeSet<u32> {
+        self.cameras
+            .values()
+            .map(|camera| camera.sensor_id)
+            .collect()
+    }
+
+    /// Group the camera IDs by the sensor ID.
+    ///
+    /// The camera IDs in each group are in the order of [`SparseViewDataset::cameras`].
+    pub fn camera_ids_by_sensor(&self) -> BTreeMap<u32, Vec<u32>> {
+        self.cameras
+            .values()
+            .fold(BTreeMap::new(), |mut groups, camera| {
+                groups
+                    .entry(camera.sensor_id)
+                    .or_insert_with(Vec::new)
+                    .push(camera.camera_id);
+                groups
+            })
+    }
+
+    /// Return the dataset with only the cameras of the sensors.
+    ///
+    /// The points are kept.
+    pub fn with_sensors(
+        &self,
+        sensor_ids: &BTreeSet<u32>,
+    ) -> Self {
+        Self {
+            cameras: self
+                .cameras
+                .iter()
+                .filter(|(_, camera)| sensor_ids.contains(&camera.sensor_id))
+                .map(|(id, camera)| (*id, camera.to_owned()))
+                .collect(),
+            points: self.points.to_owned(),
+        }
+    }
+
+    /// Split the dataset by the sensors.
+    ///
+    /// All the cameras of a sensor fall into the same side,
+    /// so no sensor is shared between the splits.
+    ///
+    /// ## Returns
+    ///
+    /// The datasets with the cameras of the sensors not in and in `sensor_ids`.
+    pub fn split_by_sensors(
+        &self,
+        sensor_ids: &BTreeSet<u32>,
+    ) -> (Self, Self) {
+        let sensor_ids_other = self
+            .sensor_ids()
+            .difference(sensor_ids)
+            .copied()
+            .collect::<BTreeSet<_>>();
+
+        (
+            self.with_sensors(&sensor_ids_other),
+            self.with_sensors(sensor_ids),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn camera_ids_by_sensor() {
+        use super::*;
+
+        let dataset = SparseViewDataset {
+            cameras: [(1, 10), (2, 20), (3, 10), (4, 30), (5, 20)]
+                .into_iter()
+                .map(|(camera_id, sensor_id)| {
+                    let camera = Camera {
+                        camera_id,
+                        sensor_id,
+                        ..Default::default()
+                    };
+                    (camera_id, camera)
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let target = [10, 20, 30].into();
+        let output = dataset.sensor_ids();
+        assert_eq!(output, target);
+
+        let target = [(10, vec![1, 3]), (20, vec![2, 5]), (30, vec![4])].into();
+        let output = dataset.camera_ids_by_sensor();
+        assert_eq!(output, target);
+
+        let (train, test) = dataset.split_by_sensors(&[20].into());
+        assert_eq!(train.cameras.keys().copied().collect::<Vec<_>>(), [1, 3, 4]);
+        assert_eq!(test.cameras.keys().copied().collect::<Vec<_>>(), [2, 5]);
+        assert_eq!(train.points, dataset.points);
+        assert_eq!(test.points, dataset.points);
+    }
+}
@@ -0,0 +1,345 @@
+//! Synthetic sparse view datasets rendered from a known 3DGS scene.
+//!
+//! Training from a random initialization against such a dataset is
+//! a ground-truth convergence test without any external data.
+
+pub use super::*;
+
+use burn::{
+    config::Config,
+    tensor::{backend::Backend, Tensor},
+};
+use gausplat_renderer::scene::gaussian_3d::{
+    backend::Autodiff,
+    render::{Gaussian3dRenderOptions, Gaussian3dRenderer},
+    Gaussian3dScene, SEED,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{f64::consts::TAU, io::Cursor};
+
+/// Configuration for synthesizing a sparse view dataset.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub struct SyntheticDatasetConfig {
+    /// Count of cameras.
+    #[config(default = "32")]
+    pub camera_count: usize,
+    /// Placement of cameras.
+    #[config(default = "CameraPlacement::Orbit")]
+    pub camera_placement: CameraPlacement,
+    /// Center which all the cameras look at.
+    #[config(default = "[0.0, 0.0, 0.0]")]
+    pub center: [f64; 3],
+    /// Elevation angle in radians of the orbit.
+    #[config(default = "0.3")]
+    pub elevation: f64,
+    /// Field of view in radians of the larger image side.
+    #[config(default = "0.8")]
+    pub field_of_view: f64,
+    /// Image height in pixels.
+    #[config(default = "256")]
+    pub image_height: u32,
+    /// Image width in pixels.
+    #[config(default = "256")]
+    pub image_width: u32,
+    /// Count of random points inside the cube of side `radius` around the center.
+    #[config(default = "1000")]
+    pub point_count: usize,
+    /// Distance from the cameras to the center.
+    #[config(default = "4.0")]
+    pub radius: f64,
+    /// Seed for the random number generator.
+    #[config(default = "SEED")]
+    pub seed: u64,
+}
+
+/// Placement of synthetic cameras.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub enum CameraPlacement {
+    /// Evenly spaced on a horizontal circle at the elevation.
+    Orbit,
+    /// Uniformly random on the upper (`+Y`) hemisphere.
+    Hemisphere,
+}
+
+impl SyntheticDatasetConfig {
+    /// Initialize the cameras without images.
+    pub fn init_cameras(&self) -> Cameras {
+        let rng = &mut StdRng::seed_from_u64(self.seed);
+        let side_max = self.image_width.max(self.image_height) as f64;
+        let tan_fov_half = (self.field_of_view / 2.0).tan();
+        let field_of_view_x =
+            (self.image_width as f64 / side_max * tan_fov_half).atan() * 2.0;
+        let field_of_view_y =
+            (self.image_height as f64 / side_max * tan_fov_half).atan() * 2.0;
+
+        (0..self.camera_count)
+            .map(|index| {
+                let direction = match self.camera_placement {
+                    CameraPlacement::Orbit => {
+                        let azimuth = TAU * index as f64 / self.camera_count as f64;
+                        let (sin_e, cos_e) = self.elevation.sin_cos();
+                        [cos_e * azimuth.cos(), sin_e, cos_e * azimuth.sin()]
+                    },
+                    CameraPlacement::Hemisphere => {
+                        let y = rng.gen_range(0.0..=1.0_f64);
+                        let azimuth = rng.gen_range(0.0..TAU);
+                        let rho = (1.0 - y * y).max(0.0).sqrt();
+                        [rho * azimuth.cos(), y, rho * azimuth.sin()]
+                    },
+                };
+                let view_position =
+                    [0, 1, 2].map(|i| self.center[i] + self.radius * direction[i]);
+                let (view_rotation, view_translation) =
+                    look_at(&view_position, &self.center);
+
+                let id = index as u32;
+                let camera = Camera {
+                    camera_id: id,
+                    depth: None,
+                    image: Image {
+                        image_file_path: format!("synthetic/{id:05}.png").into(),
+                        image_id: id,
+                        ..Default::default()
+                    },
+                    sensor_id: 0,
+                    view: View {
+                        field_of_view_x,
+                        field_of_view_y,
+                        image_height: self.image_height,
+                        image_width: self.image_width,
+                        view_id: id,
+                        view_position,
+                        view_transform: View::transform(
+                            &view_rotation,
+                            &view_translation,
+                        ),
+                    },
+                };
+
+                (id, camera)
+            })
+            .collect()
+    }
+
+    /// Synthesize the dataset by rendering the scene from the cameras.
+    ///
+    /// The images are encoded in PNG, and the points are random inside
+    /// the cube of side [`SyntheticDatasetConfig::radius`] around the center.
+    pub fn init<B: Backend>(
+        &self,
+        scene: &Gaussian3dScene<Autodiff<B>>,
+        options: &Gaussian3dRenderOptions,
+    ) -> Result<SparseViewDataset, Error>
+    where
+        Gaussian3dScene<Autodiff<B>>: Gaussian3dRenderer<B>,
+    {
+        self.init_with(|view| Ok(scene.render(view, options)?.colors_rgb_2d.inner()))
+    }
+
+    /// Synthesize the dataset with the images rendered by `render`.
+    ///
+    /// `render` returns the RGB tensor `[H, W, 3]` in `[0, 1]` of the view.
+    /// See [`SyntheticDatasetConfig::init`] for the details.
+    pub fn init_with<B: Backend, F: FnMut(&View) -> Result<Tensor<B, 3>, Error>>(
+        &self,
+        mut render: F,
+    ) -> Result<SparseViewDataset, Error> {
+        let mut cameras = self.init_cameras();
+
+        for camera in cameras.values_mut() {
+            camera.image.image_encoded =
+                encode_rgb_png(render(&camera.view)?, &camera.view)?;
+        }
+
+        let radius_half = self.radius / 2.0;
+        let bounds = BoundingBox {
+            max: self.center.map(|v| v + radius_half),
+            min: self.center.map(|v| v - radius_half),
+        };
+        let points = PointsInitializerConfig::default()
+            .with_count(self.point_count)
+            .with_seed(self.seed)
+            .init_in_box(&bounds);
+
+        Ok(SparseViewDataset { cameras, points })
+    }
+}
+
+/// Return the view rotation (row-major) and translation of the camera at
+/// `position` looking at `target` with `+Y` as the up direction.
+pub fn look_at(
+    position: &[f64; 3],
+    target: &[f64; 3],
+) -> ([[f64; 3]; 3], [f64; 3]) {
+    let normalize = |v: [f64; 3]| {
+        let norm = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        v.map(|v| v / norm)
+    };
+    let cross = |a: &[f64; 3], b: &[f64; 3]| {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+
+    // NOTE: The view space is X right, Y down and Z forward.
+    let forward = normalize([0, 1, 2].map(|i| target[i] - position[i]));
+    let up = if forward[0].abs() < 1e-9 && forward[2].abs() < 1e-9 {
+        [0.0, 0.0, -1.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+    let right = normalize(cross(&forward, &up));
+    let down = cross(&forward, &right);
+    let rotation = [right, down, forward];
+    let translation = [0, 1, 2].map(|i| {
+        -(rotation[i][0] * position[0]
+            + rotation[i][1] * position[1]
+            + rotation[i][2] * position[2])
+    });
+
+    (rotation, translation)
+}
+
+/// Encode the RGB tensor `[H, W, 3]` in `[0, 1]` to PNG.
+pub fn encode_rgb_png<B: Backend>(
+    colors_rgb_2d: Tensor<B, 3>,
+    view: &View,
+) -> Result<Vec<u8>, Error> {
+    let dims = colors_rgb_2d.dims();
+    let colors_rgb_2d = colors_rgb_2d
+        .into_data()
+        .iter::<f32>()
+        .map(|value| (value * 255.0).round().clamp(0.0, 255.0) as u8)
+        .collect::<Vec<_>>();
+    let image =
+        image::RgbImage::from_raw(view.image_width, view.image_height, colors_rgb_2d)
+            .ok_or_else(|| {
+                Error::MismatchedTensorShape(
+                    dims.to_vec(),
+                    vec![view.image_height as usize, view.image_width as usize, 3],
+                )
+            })?;
+
+    let mut image_encoded = vec![];
+    image.write_to(
+        &mut Cursor::new(&mut image_encoded),
+        image::ImageFormat::Png,
+    )?;
+
+    Ok(image_encoded)
+}
+
+impl Default for SyntheticDatasetConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn init_cameras() {
+        use super::*;
+
+        for camera_placement in [CameraPlacement::Orbit, CameraPlacement::Hemisphere] {
+            let config = SyntheticDatasetConfig::default()
+                .with_camera_count(12)
+                .with_camera_placement(camera_placement)
+                .with_center([1.0, 2.0, 3.0])
+                .with_image_width(320)
+                .with_image_height(240);
+            let cameras = config.init_cameras();
+            assert_eq!(cameras.len(), 12);
+
+            cameras.values().for_each(|camera| {
+                // The center is projected to the image center
+                let [x, y, z] = camera.to_view_position(&config.center);
+                assert!(x.abs() < 1e-9 && y.abs() < 1e-9, "{camera:?}");
+                assert!((z - config.radius).abs() < 1e-9, "{camera:?}");
+                assert!(camera.view.view_position[1] >= config.center[1]);
+                assert!(camera.view.field_of_view_x > camera.view.field_of_view_y);
+            });
+
+            let target = cameras;
+            let output = config.init_cameras();
+            assert_eq!(output, target);
+        }
+    }
+
+    #[test]
+    fn encode_rgb_png() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let view = View {
+            image_height: 2,
+            image_width: 3,
+            ..Default::default()
+        };
+        let image = Image {
+            image_encoded: super::encode_rgb_png(
+                Tensor::<NdArray, 3>::ones([2, 3, 3], &Default::default()),
+                &view,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(image.decode_dimensions().unwrap(), (3, 2));
+
+        let error = super::encode_rgb_png(
+            Tensor::<NdArray, 3>::ones([1, 1, 3], &Default::default()),
+            &view,
+        );
+        assert!(
+            matches!(
+                &error,
+                Err(Error::MismatchedTensorShape(dims, dims_target))
+                    if dims == &[1, 1, 3] && dims_target == &[2, 3, 3]
+            ),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn init_with() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let config = SyntheticDatasetConfig::default()
+            .with_camera_count(3)
+            .with_image_width(4)
+            .with_image_height(2)
+            .with_point_count(5);
+        let mut view_ids = vec![];
+        let dataset = config
+            .init_with(|view| {
+                view_ids.push(view.view_id);
+                Ok(Tensor::<NdArray, 3>::full(
+                    [view.image_height as usize, view.image_width as usize, 3],
+                    0.5,
+                    &Default::default(),
+                ))
+            })
+            .unwrap();
+        assert_eq!(view_ids, [0, 1, 2]);
+        assert_eq!(dataset.points.len(), 5);
+
+        let target = config.init_cameras();
+        dataset
+            .cameras
+            .values()
+            .zip(target.values())
+            .for_each(|(output, target)| {
+                assert_eq!(output.view, target.view);
+                assert_eq!(output.image.decode_dimensions().unwrap(), (4, 2));
+            });
+
+        // The rendering errors are propagated
+        config
+            .init_with::<NdArray, _>(|_| Err(std::io::Error::other("render").into()))
+            .unwrap_err();
+    }
+}
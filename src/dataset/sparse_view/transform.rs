@@ -0,0 +1,683 @@
+//! Similarity transformation for sparse view.
+
+pub use super::*;
+
+use burn::tensor::{
+    backend::{AutodiffBackend, Backend},
+    Tensor,
+};
+use gausplat_renderer::scene::gaussian_3d::Gaussian3dScene;
+
+/// A similarity transformation in 3D space.
+///
+/// `x' = scale * rotation * x + translation`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SimilarityTransform {
+    /// Rotation matrix in row-major order.
+    pub rotation: [[f64; 3]; 3],
+    /// Uniform scale. It should be positive.
+    pub scale: f64,
+    /// Translation vector.
+    pub translation: [f64; 3],
+}
+
+impl SimilarityTransform {
+    /// The identity transformation.
+    pub const IDENTITY: Self = Self {
+        rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        scale: 1.0,
+        translation: [0.0, 0.0, 0.0],
+    };
+
+    /// Transform the position.
+    #[inline]
+    pub fn apply(
+        &self,
+        position: &[f64; 3],
+    ) -> [f64; 3] {
+        let rotated = mul_matrix_vector(&self.rotation, position);
+        [0, 1, 2].map(|i| self.scale * rotated[i] + self.translation[i])
+    }
+
+    /// Transform the points of the scene.
+    ///
+    /// ## Details
+    ///
+    /// The positions are transformed, the rotations are composed with the rotation,
+    /// and the scalings are multiplied by the scale.
+    /// The [inverse](SimilarityTransform::inverse) maps the scene back.
+    ///
+    /// The colors SH are not rotated, so it fails with
+    /// [`Error::UnsupportedColorsShDegree`] if the rotation is not the identity
+    /// and the colors SH have a non-zero coefficient of degree above 0.
+    pub fn apply_to_scene<AB: AutodiffBackend>(
+        &self,
+        scene: &mut Gaussian3dScene<AB>,
+    ) -> Result<&Self, Error> {
+        if self.rotation != Self::IDENTITY.rotation {
+            let colors_sh_degree = get_colors_sh_degree(scene.colors_sh.val().inner());
+            if colors_sh_degree > 0 {
+                return Err(Error::UnsupportedColorsShDegree(colors_sh_degree));
+            }
+        }
+
+        let points =
+            [&scene.positions, &scene.rotations, &scene.scalings].map(|p| p.val());
+        let is_points_require_grad = points.each_ref().map(|p| p.is_require_grad());
+        let points = self
+            .apply_to_points(points.map(|p| p.inner()))
+            .into_iter()
+            .zip(is_points_require_grad)
+            .map(|(p, is_require_grad)| {
+                Tensor::from_inner(p).set_require_grad(is_require_grad)
+            })
+            .collect::<Vec<_>>();
+        let [positions, rotations, scalings] = points.try_into().expect("3 parameters");
+
+        scene
+            .set_inner_positions(positions)
+            .set_inner_rotations(rotations)
+            .set_inner_scalings(scalings);
+
+        Ok(self)
+    }
+
+    /// Transform the inner points.
+    ///
+    /// The order is positions `[P, 3]`, rotations `[P, 4]` and scalings `[P, 3]`.
+    /// The rotations are quaternions in the order of `[x, y, z, w]`.
+    ///
+    /// See [`SimilarityTransform::apply_to_scene`] for the details.
+    pub fn apply_to_points<B: Backend>(
+        &self,
+        points: [Tensor<B, 2>; 3],
+    ) -> [Tensor<B, 2>; 3] {
+        let [positions, rotations, scalings] = points;
+        let device = positions.device();
+
+        // x' = s * x * R^T + t
+        let rotation_transposed = transpose(&self.rotation);
+        let positions = Gaussian3dScene::make_inner_positions(
+            Gaussian3dScene::make_positions(positions)
+                .matmul(Tensor::from_floats(rotation_transposed, &device))
+                .mul_scalar(self.scale)
+                .add(Tensor::from_floats([self.translation], &device)),
+        );
+
+        // q' = q_R * q, which is linear in q
+        let [x, y, z, w] = quaternion_from_matrix(&self.rotation);
+        let product_left_transposed =
+            [[w, z, -y, -x], [-z, w, x, -y], [y, -x, w, -z], [x, y, z, w]];
+        let rotations =
+            rotations.matmul(Tensor::from_floats(product_left_transposed, &device));
+
+        // ln(s') = ln(s) + ln(scale)
+        let scalings = Gaussian3dScene::make_inner_scalings(
+            Gaussian3dScene::make_scalings(scalings).mul_scalar(self.scale),
+        );
+
+        [positions, rotations, scalings]
+    }
+
+    /// Return the transformation which applies `self` and then `other`.
+    pub fn then(
+        &self,
+        other: &Self,
+    ) -> Self {
+        let rotation = mul_matrix_matrix(&other.rotation, &self.rotation);
+        let scale = other.scale * self.scale;
+        let translation = other.apply(&self.translation);
+        Self {
+            rotation,
+            scale,
+            translation,
+        }
+    }
+
+    /// Return the inverse transformation.
+    pub fn inverse(&self) -> Self {
+        let rotation = transpose(&self.rotation);
+        let scale = self.scale.recip();
+        let rotated = mul_matrix_vector(&rotation, &self.translation);
+        let translation = rotated.map(|v| -v * scale);
+        Self {
+            rotation,
+            scale,
+            translation,
+        }
+    }
+
+    /// Return the rotation which aligns the unit vector `from` to the unit vector `to`.
+    pub fn rotation_between(
+        from: &[f64; 3],
+        to: &[f64; 3],
+    ) -> [[f64; 3]; 3] {
+        // Rodrigues' rotation formula: R = I + [v]x + [v]x^2 / (1 + c)
+        let v = cross(from, to);
+        let c = dot(from, to);
+
+        if c <= -1.0 + 1e-12 {
+            // Rotating by pi around an axis orthogonal to `from`
+            let axis = if from[0].abs() < 0.9 {
+                cross(from, &[1.0, 0.0, 0.0])
+            } else {
+                cross(from, &[0.0, 1.0, 0.0])
+            };
+            let axis = normalize(&axis);
+            return [0, 1, 2].map(|i| {
+                [0, 1, 2]
+                    .map(|j| 2.0 * axis[i] * axis[j] - if i == j { 1.0 } else { 0.0 })
+            });
+        }
+
+        let k = [[0.0, -v[2], v[1]], [v[2], 0.0, -v[0]], [-v[1], v[0], 0.0]];
+        let k2 = mul_matrix_matrix(&k, &k);
+        [0, 1, 2].map(|i| {
+            [0, 1, 2].map(|j| {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                identity + k[i][j] + k2[i][j] / (1.0 + c)
+            })
+        })
+    }
+}
+
+/// Transformation operations
+impl SparseViewDataset {
+    /// Apply the similarity transformation to all the views and points.
+    ///
+    /// ## Details
+    ///
+    /// The view space is scaled along with the world space,
+    /// so the projected images are unchanged.
+    pub fn transform(
+        &mut self,
+        transform: &SimilarityTransform,
+    ) -> &mut Self {
+        let rotation_inverse = transpose(&transform.rotation);
+
+        self.cameras.values_mut().for_each(|camera| {
+            // R' = R_v * R^T
+            let view_rotation =
+                mul_matrix_matrix(&camera.view_rotation(), &rotation_inverse);
+            // t' = s * t_v - R' * t
+            let view_translation = camera.view_translation();
+            let offset = mul_matrix_vector(&view_rotation, &transform.translation);
+            let view_translation =
+                [0, 1, 2].map(|i| transform.scale * view_translation[i] - offset[i]);
+
+            camera.view.view_position = transform.apply(&camera.view.view_position);
+            camera.view.view_transform =
+                View::transform(&view_rotation, &view_translation);
+        });
+
+        self.points.iter_mut().for_each(|point| {
+            point.position = transform.apply(&point.position);
+        });
+
+        self
+    }
+
+    /// Normalize the dataset by a similarity transformation:
+    ///
+    /// 1. Centering the cameras at the origin.
+    /// 2. Aligning the average up vector of the cameras to `+Y`.
+    /// 3. Scaling the cameras to the unit radius.
+    ///
+    /// ## Returns
+    ///
+    /// The transformation applied. Its [inverse](SimilarityTransform::inverse)
+    /// maps the normalized space back to the original space.
+    pub fn normalize(&mut self) -> SimilarityTransform {
+        let camera_count = self.cameras.len();
+        if camera_count == 0 {
+            return SimilarityTransform::IDENTITY;
+        }
+
+        let center = self
+            .cameras
+            .values()
+            .fold([0.0; 3], |sum, camera| {
+                let p = &camera.view.view_position;
+                [0, 1, 2].map(|i| sum[i] + p[i])
+            })
+            .map(|v| v / camera_count as f64);
+
+        // NOTE: The Y axis of the view space points downward.
+        let up = self.cameras.values().fold([0.0; 3], |sum, camera| {
+            let down = camera.view_rotation()[1];
+            [0, 1, 2].map(|i| sum[i] - down[i])
+        });
+        let rotation = if dot(&up, &up) > 0.0 {
+            SimilarityTransform::rotation_between(&normalize(&up), &[0.0, 1.0, 0.0])
+        } else {
+            SimilarityTransform::IDENTITY.rotation
+        };
+
+        let radius = self
+            .cameras
+            .values()
+            .map(|camera| {
+                let p = &camera.view.view_position;
+                (0..3)
+                    .map(|i| (p[i] - center[i]).powi(2))
+                    .sum::<f64>()
+                    .sqrt()
+            })
+            .fold(0.0, f64::max);
+        let scale = if radius > 0.0 { radius.recip() } else { 1.0 };
+
+        let rotated = mul_matrix_vector(&rotation, &center);
+        let transform = SimilarityTransform {
+            rotation,
+            scale,
+            translation: rotated.map(|v| -v * scale),
+        };
+
+        self.transform(&transform);
+
+        transform
+    }
+}
+
+#[inline]
+fn cross(
+    a: &[f64; 3],
+    b: &[f64; 3],
+) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Return the highest degree of the colors SH with a non-zero coefficient.
+///
+/// The colors SH are `[P, (D + 1)^2 * 3]` with the RGB channels innermost,
+/// so the coefficient `k` is of degree `floor(sqrt(k))`.
+fn get_colors_sh_degree<B: Backend>(colors_sh: Tensor<B, 2>) -> u32 {
+    if colors_sh.dims()[0] == 0 {
+        return 0;
+    }
+
+    colors_sh
+        .abs()
+        .max_dim(0)
+        .into_data()
+        .iter::<f64>()
+        .enumerate()
+        .filter(|(_, value)| *value != 0.0)
+        .map(|(index, _)| ((index / 3) as f64).sqrt() as u32)
+        .max()
+        .unwrap_or_default()
+}
+
+#[inline]
+fn dot(
+    a: &[f64; 3],
+    b: &[f64; 3],
+) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[inline]
+fn mul_matrix_matrix(
+    a: &[[f64; 3]; 3],
+    b: &[[f64; 3]; 3],
+) -> [[f64; 3]; 3] {
+    [0, 1, 2].map(|i| [0, 1, 2].map(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+#[inline]
+fn mul_matrix_vector(
+    a: &[[f64; 3]; 3],
+    v: &[f64; 3],
+) -> [f64; 3] {
+    [0, 1, 2].map(|i| dot(&a[i], v))
+}
+
+#[inline]
+fn normalize(v: &[f64; 3]) -> [f64; 3] {
+    let norm = dot(v, v).sqrt();
+    v.map(|v| v / norm)
+}
+
+/// Return the unit quaternion `[x, y, z, w]` of the rotation matrix.
+///
+/// The scalar part `w` is non-negative, as COLMAP prefers.
+pub(crate) fn quaternion_from_matrix(r: &[[f64; 3]; 3]) -> [f64; 4] {
+    let trace = r[0][0] + r[1][1] + r[2][2];
+    let quaternion = if trace > 0.0 {
+        let k = 2.0 * (1.0 + trace).sqrt();
+        [
+            (r[2][1] - r[1][2]) / k,
+            (r[0][2] - r[2][0]) / k,
+            (r[1][0] - r[0][1]) / k,
+            k / 4.0,
+        ]
+    } else if r[0][0] > r[1][1] && r[0][0] > r[2][2] {
+        let k = 2.0 * (1.0 + r[0][0] - r[1][1] - r[2][2]).sqrt();
+        [
+            k / 4.0,
+            (r[0][1] + r[1][0]) / k,
+            (r[0][2] + r[2][0]) / k,
+            (r[2][1] - r[1][2]) / k,
+        ]
+    } else if r[1][1] > r[2][2] {
+        let k = 2.0 * (1.0 - r[0][0] + r[1][1] - r[2][2]).sqrt();
+        [
+            (r[0][1] + r[1][0]) / k,
+            k / 4.0,
+            (r[1][2] + r[2][1]) / k,
+            (r[0][2] - r[2][0]) / k,
+        ]
+    } else {
+        let k = 2.0 * (1.0 - r[0][0] - r[1][1] + r[2][2]).sqrt();
+        [
+            (r[0][2] + r[2][0]) / k,
+            (r[1][2] + r[2][1]) / k,
+            k / 4.0,
+            (r[1][0] - r[0][1]) / k,
+        ]
+    };
+
+    if quaternion[3] < 0.0 {
+        quaternion.map(|v| -v)
+    } else {
+        quaternion
+    }
+}
+
+#[inline]
+fn transpose(a: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    [0, 1, 2].map(|i| [0, 1, 2].map(|j| a[j][i]))
+}
+
+impl Default for SimilarityTransform {
+    #[inline]
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn assert_approx_eq<const N: usize>(
+        output: &[f64; N],
+        target: &[f64; N],
+    ) {
+        (0..N).for_each(|i| {
+            assert!(
+                (output[i] - target[i]).abs() < 1e-9,
+                "{output:?} != {target:?}"
+            );
+        });
+    }
+
+    fn dataset() -> super::SparseViewDataset {
+        use super::*;
+
+        let rotation = SimilarityTransform::rotation_between(
+            &normalize(&[1.0, 2.0, 3.0]),
+            &[0.0, 0.0, 1.0],
+        );
+        let cameras = [[3.0, 1.0, 4.0], [1.0, 5.0, 9.0], [2.0, 6.0, 5.0]]
+            .into_iter()
+            .enumerate()
+            .map(|(id, view_position)| {
+                let rotated = mul_matrix_vector(&rotation, &view_position);
+                let view_translation = rotated.map(|v| -v);
+                let camera = Camera {
+                    camera_id: id as u32,
+                    view: View {
+                        field_of_view_x: 1.0,
+                        field_of_view_y: 1.0,
+                        image_height: 8,
+                        image_width: 8,
+                        view_id: id as u32,
+                        view_position,
+                        view_transform: View::transform(&rotation, &view_translation),
+                    },
+                    ..Default::default()
+                };
+                (id as u32, camera)
+            })
+            .collect();
+        let points = vec![Point {
+            color_rgb: [0.5; 3],
+            position: [2.0, 4.0, 8.0],
+        }];
+
+        SparseViewDataset { cameras, points }
+    }
+
+    #[test]
+    fn inverse() {
+        use super::*;
+
+        let transform = SimilarityTransform {
+            rotation: SimilarityTransform::rotation_between(
+                &normalize(&[1.0, -1.0, 0.5]),
+                &[0.0, 1.0, 0.0],
+            ),
+            scale: 2.5,
+            translation: [1.0, -2.0, 3.0],
+        };
+        let position = [0.3, 0.2, -0.7];
+
+        let output = transform.inverse().apply(&transform.apply(&position));
+        assert_approx_eq(&output, &position);
+
+        let output = transform.then(&transform.inverse());
+        assert_approx_eq(&[output.scale], &[1.0]);
+        assert_approx_eq(&output.translation, &[0.0; 3]);
+        (0..3).for_each(|i| {
+            assert_approx_eq(
+                &output.rotation[i],
+                &SimilarityTransform::IDENTITY.rotation[i],
+            );
+        });
+    }
+
+    #[test]
+    fn apply_to_points() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let transform = SimilarityTransform {
+            rotation: SimilarityTransform::rotation_between(
+                &normalize(&[1.0, -1.0, 0.5]),
+                &[0.0, 1.0, 0.0],
+            ),
+            scale: 2.5,
+            translation: [1.0, -2.0, 3.0],
+        };
+        let position = [0.3, 0.2, -0.7];
+        let points = [
+            Tensor::<NdArray, 2>::from_floats([position.map(|v| v as f32)], &device),
+            Tensor::from_floats([[0.0, 0.0, 0.0, 1.0], [0.1, -0.2, 0.3, 0.9]], &device),
+            Gaussian3dScene::make_inner_scalings(Tensor::from_floats(
+                [[0.5, 1.0, 2.0]],
+                &device,
+            )),
+        ];
+
+        let [positions, rotations, scalings] =
+            transform.apply_to_points(points.to_owned());
+
+        let target = Tensor::<NdArray, 2>::from_floats(
+            [transform.apply(&position).map(|v| v as f32)],
+            &device,
+        )
+        .into_data();
+        let output = Gaussian3dScene::make_positions(positions).into_data();
+        output.assert_approx_eq(&target, 5);
+
+        // The identity rotation is composed into the rotation
+        let target = Tensor::<NdArray, 1>::from_floats(
+            quaternion_from_matrix(&transform.rotation).map(|v| v as f32),
+            &device,
+        )
+        .into_data();
+        let output = rotations.slice([0..1]).squeeze::<1>(0).into_data();
+        output.assert_approx_eq(&target, 5);
+
+        let target =
+            Tensor::<NdArray, 2>::from_floats([[1.25, 2.5, 5.0]], &device).into_data();
+        let output = Gaussian3dScene::make_scalings(scalings).into_data();
+        output.assert_approx_eq(&target, 5);
+
+        // The transformation is reversible
+        let outputs = transform
+            .inverse()
+            .apply_to_points(transform.apply_to_points(points.to_owned()));
+        outputs
+            .into_iter()
+            .zip(points)
+            .for_each(|(output, target)| {
+                output.into_data().assert_approx_eq(&target.into_data(), 5);
+            });
+    }
+
+    #[test]
+    fn get_colors_sh_degree() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let mut colors_sh = [[0.0; 27]; 2];
+        colors_sh[0][..3].copy_from_slice(&[0.5, 0.25, 0.125]);
+
+        let target = 0;
+        let output = super::get_colors_sh_degree(Tensor::<NdArray, 2>::from_floats(
+            colors_sh, &device,
+        ));
+        assert_eq!(output, target);
+
+        // The coefficient 4 (green) is of degree 2
+        colors_sh[1][13] = -0.5;
+        let target = 2;
+        let output = super::get_colors_sh_degree(Tensor::<NdArray, 2>::from_floats(
+            colors_sh, &device,
+        ));
+        assert_eq!(output, target);
+
+        let target = 0;
+        let output =
+            super::get_colors_sh_degree(Tensor::<NdArray, 2>::empty([0, 27], &device));
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn quaternion_from_matrix() {
+        use super::*;
+
+        let target = [0.0, 0.0, 0.0, 1.0];
+        let output =
+            super::quaternion_from_matrix(&SimilarityTransform::IDENTITY.rotation);
+        assert_approx_eq(&output, &target);
+
+        // Rotating by pi / 2 around +Z
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        let target = [0.0, 0.0, half, half];
+        let output = super::quaternion_from_matrix(&[
+            [0.0, -1.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ]);
+        assert_approx_eq(&output, &target);
+
+        // Rotating by pi around +Z
+        let target = [0.0, 0.0, 1.0, 0.0];
+        let output = super::quaternion_from_matrix(&[
+            [-1.0, 0.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ]);
+        assert_approx_eq(&output, &target);
+
+        // Rotating by pi around +X
+        let target = [1.0, 0.0, 0.0, 0.0];
+        let output = super::quaternion_from_matrix(&[
+            [1.0, 0.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 0.0, -1.0],
+        ]);
+        assert_approx_eq(&output, &target);
+    }
+
+    #[test]
+    fn rotation_between() {
+        use super::*;
+
+        [
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.6, 0.0, 0.8],
+        ]
+        .into_iter()
+        .for_each(|from| {
+            let rotation = SimilarityTransform::rotation_between(&from, &[0.0, 1.0, 0.0]);
+            let output = mul_matrix_vector(&rotation, &from);
+            assert_approx_eq(&output, &[0.0, 1.0, 0.0]);
+        });
+    }
+
+    #[test]
+    fn transform_and_normalize() {
+        use super::*;
+
+        let source = dataset();
+        let mut dataset = source.to_owned();
+        let transform = dataset.normalize();
+
+        // The projections are unchanged
+        let point_source = &source.points[0].position;
+        let point = &dataset.points[0].position;
+        source
+            .cameras
+            .values()
+            .zip(dataset.cameras.values())
+            .for_each(|(s, c)| {
+                let output = c.to_view_position(point);
+                let target = s.to_view_position(point_source);
+                let output = [output[0] / output[2], output[1] / output[2]];
+                let target = [target[0] / target[2], target[1] / target[2]];
+                assert_approx_eq(&output, &target);
+            });
+
+        // The cameras are centered at the origin with the unit radius
+        let radius = dataset
+            .cameras
+            .values()
+            .map(|c| dot(&c.view.view_position, &c.view.view_position).sqrt())
+            .fold(0.0, f64::max);
+        assert_approx_eq(&[radius], &[1.0]);
+
+        // The average up vector is aligned to +Y
+        let up = dataset.cameras.values().fold([0.0; 3], |sum, c| {
+            let down = c.view_rotation()[1];
+            [0, 1, 2].map(|i| sum[i] - down[i])
+        });
+        assert_approx_eq(&normalize(&up), &[0.0, 1.0, 0.0]);
+
+        // The transformation is reversible
+        dataset.transform(&transform.inverse());
+        dataset
+            .cameras
+            .values()
+            .zip(source.cameras.values())
+            .for_each(|(c, s)| {
+                assert_approx_eq(&c.view.view_position, &s.view.view_position);
+                (0..4).for_each(|i| {
+                    assert_approx_eq(
+                        &c.view.view_transform[i],
+                        &s.view.view_transform[i],
+                    );
+                });
+            });
+        assert_approx_eq(&dataset.points[0].position, &source.points[0].position);
+    }
+}
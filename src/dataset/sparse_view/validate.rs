@@ -0,0 +1,332 @@
+//! Validation and diagnostics for sparse view datasets.
+
+pub use super::*;
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::{collections::BTreeSet, f64::consts::PI, fmt, path::PathBuf};
+
+/// Report of validating a sparse view dataset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    /// Count of images in the source.
+    pub image_count: usize,
+    /// Issues found.
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// An issue found in validation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    /// Image ID of the camera with the issue.
+    ///
+    /// It is `None` if the issue is not about a specific camera.
+    pub image_id: Option<u32>,
+    /// Kind of the issue.
+    pub kind: ValidationIssueKind,
+}
+
+/// Kinds of issues found in validation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationIssueKind {
+    /// The field of view (x, y) is not in `(0, π)`.
+    DegenerateFieldOfView(f64, f64),
+    /// The pose is the same as the camera of the image ID.
+    DuplicatePose(u32),
+    /// The image file cannot be read or decoded.
+    InvalidImage(String),
+    /// The image file name is not in UTF-8.
+    InvalidUtf8(String),
+    /// The image dimensions (width, height) mismatch the COLMAP camera ones.
+    MismatchedDimensions([u32; 2], [u32; 2]),
+    /// The image file path mismatches the key.
+    MismatchedImageFilePath(PathBuf, PathBuf),
+    /// The key mismatches the image ID (key, image ID).
+    MismatchedImageId(u32, u32),
+    /// The image file is missing.
+    MissingImageFile(PathBuf),
+    /// No points are visible in the camera.
+    NoVisiblePoints,
+    /// The image file is not a file.
+    NotAFile(PathBuf),
+    /// The COLMAP camera ID is unknown.
+    UnknownCameraId(u32),
+}
+
+impl ValidationReport {
+    /// Return `true` if there are no issues.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Return the image IDs of the cameras with issues.
+    pub fn image_ids_invalid(&self) -> BTreeSet<u32> {
+        self.issues
+            .iter()
+            .filter_map(|issue| issue.image_id)
+            .collect()
+    }
+
+    /// Add an issue from the error.
+    #[inline]
+    pub fn push(
+        &mut self,
+        image_id: Option<u32>,
+        error: &Error,
+    ) -> &mut Self {
+        self.issues.push(ValidationIssue {
+            image_id,
+            kind: error.into(),
+        });
+        self
+    }
+
+    /// Check the camera initialized from COLMAP.
+    ///
+    /// `camera_dimensions` are the width and height of the COLMAP camera.
+    pub fn check_camera(
+        &mut self,
+        camera: &Camera,
+        camera_dimensions: [u32; 2],
+    ) -> &mut Self {
+        let image_id = Some(camera.camera_id);
+        let view = &camera.view;
+        let image_dimensions = [view.image_width, view.image_height];
+        let is_degenerate = |fov: f64| !(fov > 0.0 && fov < PI);
+
+        if is_degenerate(view.field_of_view_x) || is_degenerate(view.field_of_view_y) {
+            self.issues.push(ValidationIssue {
+                image_id,
+                kind: ValidationIssueKind::DegenerateFieldOfView(
+                    view.field_of_view_x,
+                    view.field_of_view_y,
+                ),
+            });
+        }
+        if image_dimensions != camera_dimensions {
+            self.issues.push(ValidationIssue {
+                image_id,
+                kind: ValidationIssueKind::MismatchedDimensions(
+                    image_dimensions,
+                    camera_dimensions,
+                ),
+            });
+        }
+
+        self
+    }
+
+    /// Check the cameras against each other and against the points.
+    ///
+    /// - The later camera of the duplicate poses is reported.
+    /// - The cameras with no visible points are reported if there are points.
+    pub fn check_dataset(
+        &mut self,
+        dataset: &SparseViewDataset,
+    ) -> &mut Self {
+        const EPSILON: f64 = 1e-9;
+
+        let mut cameras = dataset.cameras.values().collect::<Vec<_>>();
+        cameras.sort_by_key(|camera| camera.camera_id);
+
+        for (index, camera) in cameras.iter().enumerate() {
+            let transform = &camera.view.view_transform;
+            let camera_duplicate = cameras[..index].iter().find(|other| {
+                let other = &other.view.view_transform;
+                (0..4).all(|i| {
+                    (0..4).all(|j| (transform[i][j] - other[i][j]).abs() < EPSILON)
+                })
+            });
+            if let Some(other) = camera_duplicate {
+                self.issues.push(ValidationIssue {
+                    image_id: Some(camera.camera_id),
+                    kind: ValidationIssueKind::DuplicatePose(other.camera_id),
+                });
+            }
+        }
+
+        if !dataset.points.is_empty() {
+            let image_ids_without_points = cameras
+                .par_iter()
+                .filter(|camera| {
+                    !dataset
+                        .points
+                        .iter()
+                        .any(|point| camera.is_in_frustum(&point.position, 0.0, f64::MAX))
+                })
+                .map(|camera| camera.camera_id)
+                .collect::<Vec<_>>();
+            self.issues
+                .extend(image_ids_without_points.into_iter().map(|image_id| {
+                    ValidationIssue {
+                        image_id: Some(image_id),
+                        kind: ValidationIssueKind::NoVisiblePoints,
+                    }
+                }));
+        }
+
+        self
+    }
+}
+
+impl From<&Error> for ValidationIssueKind {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::InvalidUtf8(name) => Self::InvalidUtf8(name.to_owned()),
+            Error::IoIsADirectory(path) => Self::NotAFile(path.to_owned()),
+            Error::MismatchedImageFilePath(path, target) => {
+                Self::MismatchedImageFilePath(path.to_owned(), target.to_owned())
+            },
+            Error::MismatchedImageId(key, id) => Self::MismatchedImageId(*key, *id),
+            Error::UnknownCameraId(id) => Self::UnknownCameraId(*id),
+            Error::UnknownImageFileName(name) => Self::MissingImageFile(name.to_owned()),
+            error => Self::InvalidImage(error.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        writeln!(
+            f,
+            "{} issues in {} images ({} invalid)",
+            self.issues.len(),
+            self.image_count,
+            self.image_ids_invalid().len(),
+        )?;
+        for issue in &self.issues {
+            match issue.image_id {
+                Some(image_id) => writeln!(f, "- image {image_id}: {:?}", issue.kind)?,
+                None => writeln!(f, "- {:?}", issue.kind)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn check_dataset() {
+        use super::*;
+
+        let rotation = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let camera = Camera {
+            view: View {
+                field_of_view_x: 1.0,
+                field_of_view_y: 1.0,
+                image_height: 8,
+                image_width: 8,
+                view_id: 0,
+                view_position: [0.0, 0.0, 0.0],
+                view_transform: View::transform(&rotation, &[0.0, 0.0, 0.0]),
+            },
+            ..Default::default()
+        };
+        let mut camera_duplicate = camera.to_owned();
+        camera_duplicate.camera_id = 1;
+        let mut camera_backward = camera.to_owned();
+        camera_backward.camera_id = 2;
+        camera_backward.view.view_position = [0.0, 0.0, 5.0];
+        camera_backward.view.view_transform =
+            View::transform(&rotation, &[0.0, 0.0, -5.0]);
+        let dataset = SparseViewDataset {
+            cameras: [(0, camera), (1, camera_duplicate), (2, camera_backward)]
+                .into_iter()
+                .collect(),
+            points: vec![Point {
+                color_rgb: [0.5; 3],
+                position: [0.0, 0.0, 1.0],
+            }],
+        };
+
+        let mut report = ValidationReport::default();
+        report.check_dataset(&dataset);
+
+        let target = vec![
+            ValidationIssue {
+                image_id: Some(1),
+                kind: ValidationIssueKind::DuplicatePose(0),
+            },
+            ValidationIssue {
+                image_id: Some(2),
+                kind: ValidationIssueKind::NoVisiblePoints,
+            },
+        ];
+        let output = report.issues.to_owned();
+        assert_eq!(output, target);
+        assert_eq!(report.image_ids_invalid(), [1, 2].into());
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn check_camera() {
+        use super::*;
+
+        let camera = Camera {
+            camera_id: 7,
+            view: View {
+                field_of_view_x: 0.0,
+                field_of_view_y: 1.0,
+                image_height: 6,
+                image_width: 8,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut report = ValidationReport::default();
+        report.check_camera(&camera, [8, 6]);
+        let target = vec![ValidationIssue {
+            image_id: Some(7),
+            kind: ValidationIssueKind::DegenerateFieldOfView(0.0, 1.0),
+        }];
+        assert_eq!(report.issues, target);
+
+        let mut report = ValidationReport::default();
+        report.check_camera(&camera, [16, 12]);
+        let target = ValidationIssueKind::MismatchedDimensions([8, 6], [16, 12]);
+        assert_eq!(report.issues[1].kind, target);
+    }
+
+    #[test]
+    fn validate_colmap() {
+        use super::*;
+
+        let report = SparseViewDataset::validate_colmap(ColmapSource::<&[u8]> {
+            cameras: Default::default(),
+            images: [Default::default()].into(),
+            images_file: [Default::default()].into(),
+            points: [Default::default()].into(),
+        })
+        .unwrap();
+        let target = ValidationReport {
+            image_count: 1,
+            issues: vec![
+                ValidationIssue {
+                    image_id: None,
+                    kind: ValidationIssueKind::NotAFile(Default::default()),
+                },
+                ValidationIssue {
+                    image_id: Some(0),
+                    kind: ValidationIssueKind::UnknownCameraId(0),
+                },
+            ],
+        };
+        assert_eq!(report, target, "{report}");
+
+        let (dataset, report) =
+            SparseViewDataset::init_from_colmap_skipping_invalid(ColmapSource::<&[u8]> {
+                cameras: Default::default(),
+                images: [Default::default()].into(),
+                images_file: Default::default(),
+                points: Default::default(),
+            })
+            .unwrap();
+        assert!(dataset.cameras.is_empty(), "{:?}", dataset.cameras);
+        assert_eq!(report.image_ids_invalid(), [0].into());
+    }
+}
@@ -5,18 +5,42 @@ use std::path::PathBuf;
 /// Error variants.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    /// Error from duplicate image file name.
+    #[error("Duplicate image file name: {0:?}")]
+    DuplicateImageFileName(PathBuf),
     /// Error from I/O operations.
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     /// Error from I/O operations (is a directory).
     #[error("IO error: is a directory: {0:?}")]
     IoIsADirectory(PathBuf),
+    /// Error from image operations.
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+    /// Error from invalid cache.
+    #[error("Invalid cache: {0}")]
+    InvalidCache(String),
+    /// Error from invalid depth map.
+    #[error("Invalid depth: {0}")]
+    InvalidDepth(String),
+    /// Error from invalid radii of a spherical shell.
+    #[error("Invalid radii: {0} and {1}. It should be 0 <= inner <= outer.")]
+    InvalidRadii(f64, f64),
+    /// Error from invalid polygon file (PLY).
+    #[error("Invalid PLY: {0}")]
+    InvalidPly(String),
+    /// Error from invalid scale factor.
+    #[error("Invalid scale factor: {0}. It should be finite and non-negative.")]
+    InvalidScaleFactor(f64),
     /// Error from invalid UTF-8 string.
     #[error("Invalid UTF-8 string: {0:?}")]
     InvalidUtf8(String),
     /// Error from [`gausplat_loader`].
     #[error("Gausplat loader error: {0}")]
     Loader(#[from] gausplat_loader::error::Error),
+    /// Error from mismatched cache hash.
+    #[error("Mismatched cache hash: {0:#x}. It should be {1:#x}.")]
+    MismatchedCacheHash(u64, u64),
     /// Error from mismatched image file path.
     #[error("Mismatched image file path: {0:?}. It should be {1:?}.")]
     MismatchedImageFilePath(PathBuf, PathBuf),
@@ -26,6 +50,12 @@ pub enum Error {
     /// Error from mismatched tensor shape.
     #[error("Mismatched tensor shape: {0:?}. It should be {1:?}.")]
     MismatchedTensorShape(Vec<usize>, Vec<usize>),
+    /// Error from non-finite values (NaN or Inf) in training.
+    #[error("Non-finite value at iteration {0} from camera ids: {1:?}")]
+    NonFiniteValue(u64, Vec<u32>),
+    /// Error from overflowed id.
+    #[error("Overflowed id: {0} + {1}. It should be at most u32::MAX.")]
+    OverflowedId(u32, u32),
     /// Error from [`gausplat_renderer`].
     #[error("Render error: {0}")]
     Render(#[from] gausplat_renderer::error::Error),
@@ -35,4 +65,7 @@ pub enum Error {
     /// Error from unknown image file name.
     #[error("Unknown image file name: {0:?}")]
     UnknownImageFileName(PathBuf),
+    /// Error from unsupported degree of colors SH.
+    #[error("Unsupported colors SH degree: {0}. It should be 0 to rotate the scene.")]
+    UnsupportedColorsShDegree(u32),
 }
@@ -0,0 +1,114 @@
+//! Gradient clipping module.
+
+pub use burn::{
+    config::Config,
+    tensor::{backend::Backend, Tensor},
+};
+
+/// Gradient clipping.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub enum GradientClipping {
+    /// Clip each value to `[-max, max]`.
+    Value(f64),
+    /// Scale the whole gradient so that its norm is at most `max`.
+    Norm(f64),
+    /// Scale each row of the gradient so that its norm is at most `max`.
+    ///
+    /// The rows are along the last dimension.
+    RowNorm(f64),
+}
+
+impl GradientClipping {
+    /// Clip the gradient.
+    pub fn clip<B: Backend, const D: usize>(
+        &self,
+        grad: Tensor<B, D>,
+    ) -> Tensor<B, D> {
+        match *self {
+            Self::Value(max) => grad.clamp(-max, max),
+            Self::Norm(max) => {
+                let norm = grad.to_owned().powf_scalar(2.0).sum().sqrt();
+                let scale = norm.clamp_min(max).recip().mul_scalar(max);
+                grad.mul(scale.reshape([1; D]))
+            },
+            Self::RowNorm(max) => {
+                let norms = grad.to_owned().powf_scalar(2.0).sum_dim(D - 1).sqrt();
+                let scales = norms.clamp_min(max).recip().mul_scalar(max);
+                grad.mul(scales)
+            },
+        }
+    }
+}
+
+/// Clip the gradient if the clipping is specified.
+#[inline]
+pub fn clip_grad<B: Backend, const D: usize>(
+    clipping: Option<GradientClipping>,
+    grad: Tensor<B, D>,
+) -> Tensor<B, D> {
+    match clipping {
+        Some(clipping) => clipping.clip(grad),
+        None => grad,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn clip() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let grad = Tensor::<NdArray, 2>::from_floats([[3.0, -4.0], [0.3, 0.4]], &device);
+
+        let target =
+            Tensor::<NdArray, 2>::from_floats([[1.0, -1.0], [0.3, 0.4]], &device)
+                .into_data();
+        let output = GradientClipping::Value(1.0)
+            .clip(grad.to_owned())
+            .into_data();
+        output.assert_approx_eq(&target, 6);
+
+        // The norm is sqrt(25.25).
+        let target = grad
+            .to_owned()
+            .div_scalar(25.25_f64.sqrt())
+            .mul_scalar(2.0)
+            .into_data();
+        let output = GradientClipping::Norm(2.0)
+            .clip(grad.to_owned())
+            .into_data();
+        output.assert_approx_eq(&target, 6);
+
+        let target = grad.to_owned().into_data();
+        let output = GradientClipping::Norm(10.0)
+            .clip(grad.to_owned())
+            .into_data();
+        output.assert_approx_eq(&target, 6);
+
+        let target =
+            Tensor::<NdArray, 2>::from_floats([[0.6, -0.8], [0.3, 0.4]], &device)
+                .into_data();
+        let output = GradientClipping::RowNorm(1.0).clip(grad).into_data();
+        output.assert_approx_eq(&target, 6);
+    }
+
+    #[test]
+    fn clip_grad() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let grad = Tensor::<NdArray, 1>::from_floats([3.0, -4.0], &device);
+
+        let target = grad.to_owned().into_data();
+        let output = super::clip_grad(None, grad.to_owned()).into_data();
+        output.assert_approx_eq(&target, 6);
+
+        let target = Tensor::<NdArray, 1>::from_floats([2.0, -2.0], &device).into_data();
+        let output =
+            super::clip_grad(Some(GradientClipping::Value(2.0)), grad).into_data();
+        output.assert_approx_eq(&target, 6);
+    }
+}
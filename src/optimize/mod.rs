@@ -1,7 +1,9 @@
 //! Optimization module.
 
 pub mod adam;
+pub mod clip;
 pub mod learning_rate;
 
 pub use adam::*;
+pub use clip::*;
 pub use learning_rate::*;
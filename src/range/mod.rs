@@ -32,6 +32,24 @@ impl RangeOptions {
             && iteration < self.end
             && (iteration - self.start) % self.step == 0
     }
+
+    /// Scale the length of the range by the ratio, keeping the start.
+    ///
+    /// The unbounded end (`u64::MAX`) is kept.
+    pub fn scale_end(
+        &self,
+        ratio: f64,
+    ) -> Self {
+        if self.end == u64::MAX || self.end <= self.start {
+            return *self;
+        }
+
+        let length = ((self.end - self.start) as f64 * ratio.max(0.0)).round() as u64;
+        Self {
+            end: self.start.saturating_add(length),
+            ..*self
+        }
+    }
 }
 
 impl Default for RangeOptions {
@@ -59,4 +77,23 @@ mod tests {
             assert_eq!(output, target, "range.has({i})");
         });
     }
+
+    #[test]
+    fn scale_end() {
+        use super::*;
+
+        let range = RangeOptions::new(500, 15000, 100);
+
+        let target = RangeOptions::new(500, 7750, 100);
+        let output = range.scale_end(0.5);
+        assert_eq!(output, target);
+
+        let target = range;
+        let output = range.scale_end(1.0);
+        assert_eq!(output, target);
+
+        let target = RangeOptions::default();
+        let output = RangeOptions::default().scale_end(0.5);
+        assert_eq!(output, target);
+    }
 }
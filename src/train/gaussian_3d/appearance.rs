@@ -0,0 +1,372 @@
+//! 3DGS appearance compensation implementation.
+
+pub use super::*;
+
+use std::collections::BTreeMap;
+
+/// Appearance compensator for 3DGS.
+///
+/// It learns an affine color transform for each camera,
+/// which absorbs the exposure and white balance shifts between images.
+#[derive(Clone, Debug)]
+pub struct Appearance<AB: AutodiffBackend> {
+    /// Configuration.
+    pub config: AppearanceConfig,
+    /// Current learning rate.
+    pub learning_rate: LearningRate,
+    /// Configuration of the optimizer for each camera.
+    pub optimizer_adam: AdamConfig,
+    /// States of each camera.
+    pub states: BTreeMap<u32, AppearanceState<AB>>,
+}
+
+/// Configuration for the appearance compensator.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub struct AppearanceConfig {
+    /// Whether to apply the transform to the render in training.
+    #[config(default = "false")]
+    pub is_enabled: bool,
+    /// Learning rate.
+    #[config(default = "1e-3.into()")]
+    pub learning_rate: LearningRateConfig,
+}
+
+/// State of the appearance compensator for a camera.
+#[derive(Clone, Debug)]
+pub struct AppearanceState<AB: AutodiffBackend> {
+    /// Current optimizer.
+    pub optimizer: Adam<AB, 2>,
+    /// Affine color transform.
+    ///
+    /// `[4, 3]`, the last row is the bias.
+    pub transform: Tensor<AB::InnerBackend, 2>,
+}
+
+/// Record for the appearance compensator.
+#[derive(Clone, Debug, Record)]
+pub struct AppearanceRecord<B: Backend> {
+    /// Learning rate.
+    pub learning_rate: LearningRateRecord,
+    /// Records of each camera.
+    pub states: Vec<AppearanceStateRecord<B>>,
+}
+
+/// Record of the appearance compensator for a camera.
+#[derive(Clone, Debug, Record)]
+pub struct AppearanceStateRecord<B: Backend> {
+    /// Camera ID.
+    pub camera_id: u32,
+    /// Optimizer.
+    pub optimizer: AdamRecord<B, 2>,
+    /// Affine color transform.
+    pub transform: Tensor<B, 2>,
+}
+
+impl AppearanceConfig {
+    /// Initialize the appearance compensator.
+    #[inline]
+    pub fn init<AB: AutodiffBackend>(
+        self,
+        optimizer_adam: AdamConfig,
+    ) -> Appearance<AB> {
+        Appearance {
+            config: self,
+            learning_rate: self.learning_rate.init(),
+            optimizer_adam,
+            states: Default::default(),
+        }
+    }
+}
+
+impl<AB: AutodiffBackend> Appearance<AB> {
+    /// Apply the affine color transform to the colors.
+    ///
+    /// ## Arguments
+    ///
+    /// * `colors_rgb_2d` - The colors with shape `[H, W, 3]`.
+    /// * `transform` - The transform with shape `[4, 3]`.
+    pub fn apply(
+        colors_rgb_2d: Tensor<AB, 3>,
+        transform: Tensor<AB, 2>,
+    ) -> Tensor<AB, 3> {
+        let [image_height, image_width, channel_count] = colors_rgb_2d.dims();
+        let pixel_count = image_height * image_width;
+        let device = &colors_rgb_2d.device();
+
+        Tensor::cat(
+            vec![
+                colors_rgb_2d.reshape([pixel_count, channel_count]),
+                Tensor::ones([pixel_count, 1], device),
+            ],
+            1,
+        )
+        .matmul(transform)
+        .reshape([image_height, image_width, 3])
+    }
+
+    /// Apply the transform of the camera to the colors.
+    ///
+    /// It returns the colors unchanged if the compensator is disabled
+    /// or the camera has no transform.
+    pub fn compensate(
+        &self,
+        camera_id: u32,
+        colors_rgb_2d: Tensor<AB, 3>,
+    ) -> Tensor<AB, 3> {
+        if !self.config.is_enabled {
+            return colors_rgb_2d;
+        }
+
+        match self.states.get(&camera_id) {
+            Some(state) => Self::apply(
+                colors_rgb_2d,
+                Tensor::from_inner(state.transform.to_owned()),
+            ),
+            None => colors_rgb_2d,
+        }
+    }
+
+    /// Get the trainable transform of the camera.
+    ///
+    /// The transform is initialized to identity if the camera has none.
+    pub fn get_transform(
+        &mut self,
+        camera_id: u32,
+        device: &AB::Device,
+    ) -> Tensor<AB, 2> {
+        let optimizer_adam = self.optimizer_adam;
+        let state = self
+            .states
+            .entry(camera_id)
+            .or_insert_with(|| AppearanceState {
+                optimizer: optimizer_adam.init(),
+                transform: Tensor::<AB::InnerBackend, 2>::cat(
+                    vec![Tensor::eye(3, device), Tensor::zeros([1, 3], device)],
+                    0,
+                ),
+            });
+
+        Tensor::from_inner(state.transform.to_owned()).require_grad()
+    }
+
+    /// Optimize the transform of the camera.
+    ///
+    /// The learning rate is updated per optimization step by the trainer.
+    pub fn optimize(
+        &mut self,
+        camera_id: u32,
+        transform: Tensor<AB, 2>,
+        grads: &mut AB::Gradients,
+    ) -> &mut Self {
+        let Some(grad) = transform.grad_remove(grads) else {
+            return self;
+        };
+        let Some(state) = self.states.get_mut(&camera_id) else {
+            return self;
+        };
+
+        state.transform = state
+            .optimizer
+            .update(*self.learning_rate, transform, grad)
+            .inner();
+
+        self
+    }
+
+    /// Transfer the appearance compensator to the device.
+    pub fn to_device(
+        mut self,
+        device: &AB::Device,
+    ) -> Self {
+        self.states = self
+            .states
+            .into_iter()
+            .map(|(camera_id, mut state)| {
+                state.optimizer = state.optimizer.to_device(device);
+                state.transform = state.transform.to_device(device);
+                (camera_id, state)
+            })
+            .collect();
+
+        self
+    }
+
+    /// Load the record.
+    pub fn load_record(
+        &mut self,
+        record: AppearanceRecord<AB::InnerBackend>,
+    ) -> &mut Self {
+        self.learning_rate.load_record(record.learning_rate);
+        self.states = record
+            .states
+            .into_iter()
+            .map(|record| {
+                let mut optimizer = self.optimizer_adam.init();
+                optimizer.load_record(record.optimizer);
+                let state = AppearanceState {
+                    optimizer,
+                    transform: record.transform,
+                };
+                (record.camera_id, state)
+            })
+            .collect();
+
+        self
+    }
+
+    /// Unload the record.
+    pub fn into_record(self) -> AppearanceRecord<AB::InnerBackend> {
+        AppearanceRecord {
+            learning_rate: self.learning_rate.into_record(),
+            states: self
+                .states
+                .into_iter()
+                .map(|(camera_id, state)| AppearanceStateRecord {
+                    camera_id,
+                    optimizer: state.optimizer.into_record(),
+                    transform: state.transform,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<B: Backend> Gaussian3dTrainer<Autodiff<B>>
+where
+    Gaussian3dScene<Autodiff<B>>: Gaussian3dRenderer<B>,
+{
+    /// Fit the appearance transform of a camera with the scene frozen.
+    ///
+    /// It is useful for evaluating on held-out views,
+    /// whose exposure is unknown to the trained transforms.
+    pub fn fit_appearance(
+        &mut self,
+        scene: &Gaussian3dScene<Autodiff<B>>,
+        camera: &sparse_view::Camera,
+        iteration_count: u64,
+    ) -> Result<&mut Self, Error> {
+        let colors_rgb_2d = scene
+            .render(&camera.view, &self.options_renderer)?
+            .colors_rgb_2d
+            .detach();
+        let device = &colors_rgb_2d.device();
+        let colors_rgb_2d_target = camera
+            .image
+            .decode_rgb_tensor(device)?
+            .set_require_grad(false);
+
+        for _ in 0..iteration_count {
+            let transform = self.appearance.get_transform(camera.camera_id, device);
+            let loss = self.metric_optimization_coarse.evaluate(
+                Appearance::apply(colors_rgb_2d.to_owned(), transform.to_owned()),
+                colors_rgb_2d_target.to_owned(),
+            );
+            let grads = &mut loss.backward();
+            self.appearance.optimize(camera.camera_id, transform, grads);
+        }
+
+        Ok(self)
+    }
+}
+
+impl<AB: AutodiffBackend> Default for Appearance<AB> {
+    #[inline]
+    fn default() -> Self {
+        AppearanceConfig::default().init(Default::default())
+    }
+}
+
+impl Default for AppearanceConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn apply_identity() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let mut appearance = Appearance::<Autodiff<NdArray>>::default();
+
+        let colors_rgb_2d = Tensor::<Autodiff<NdArray>, 3>::random(
+            [4, 5, 3],
+            Distribution::Uniform(0.0, 1.0),
+            &device,
+        );
+        let transform = appearance.get_transform(0, &device);
+
+        let target = colors_rgb_2d.to_owned().into_data();
+        let output = Appearance::apply(colors_rgb_2d, transform).into_data();
+        output.assert_approx_eq(&target, 6);
+    }
+
+    #[test]
+    fn compensate_disabled() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let mut appearance = Appearance::<Autodiff<NdArray>>::default();
+        appearance.get_transform(0, &device);
+        appearance.states.get_mut(&0).unwrap().transform = Tensor::zeros([4, 3], &device);
+
+        let colors_rgb_2d = Tensor::<Autodiff<NdArray>, 3>::ones([2, 2, 3], &device);
+
+        let target = colors_rgb_2d.to_owned().into_data();
+        let output = appearance
+            .compensate(0, colors_rgb_2d.to_owned())
+            .into_data();
+        output.assert_approx_eq(&target, 6);
+
+        appearance.config.is_enabled = true;
+        let target =
+            Tensor::<Autodiff<NdArray>, 3>::zeros([2, 2, 3], &device).into_data();
+        let output = appearance.compensate(0, colors_rgb_2d).into_data();
+        output.assert_approx_eq(&target, 6);
+    }
+
+    #[test]
+    fn optimize_exposure() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let metric = metric::MeanAbsoluteError::init();
+        let mut appearance = AppearanceConfig::default()
+            .with_learning_rate(1e-2.into())
+            .init::<Autodiff<NdArray>>(Default::default());
+
+        let colors_rgb_2d = Tensor::<Autodiff<NdArray>, 3>::random(
+            [8, 8, 3],
+            Distribution::Uniform(0.0, 1.0),
+            &device,
+        );
+        let colors_rgb_2d_target =
+            colors_rgb_2d.to_owned().mul_scalar(0.5).add_scalar(0.2);
+
+        let mut losses = vec![];
+        for _ in 0..200 {
+            let transform = appearance.get_transform(7, &device);
+            let loss = metric.evaluate(
+                Appearance::apply(colors_rgb_2d.to_owned(), transform.to_owned()),
+                colors_rgb_2d_target.to_owned(),
+            );
+            losses.push(loss.to_owned().into_scalar());
+            appearance.optimize(7, transform, &mut loss.backward());
+        }
+
+        let loss_first = losses.first().copied().unwrap();
+        let loss_last = losses.last().copied().unwrap();
+        assert!(loss_last < loss_first * 0.5, "{loss_first} -> {loss_last}");
+
+        let record = appearance.into_record();
+        assert_eq!(record.states.len(), 1);
+        assert_eq!(record.states[0].camera_id, 7);
+        assert!(record.states[0].optimizer.is_some());
+    }
+}
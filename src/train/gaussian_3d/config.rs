@@ -6,6 +6,27 @@ pub use crate::optimize::{AdamConfig, LearningRateConfig};
 /// 3DGS trainer configuration.
 #[derive(Config, Copy, Debug, PartialEq)]
 pub struct Gaussian3dTrainerConfig {
+    /// Appearance compensator configuration.
+    #[config(default = "Default::default()")]
+    pub appearance: AppearanceConfig,
+    /// Gradient clipping for colors SH.
+    #[config(default = "None")]
+    pub clipping_colors_sh: Option<GradientClipping>,
+    /// Gradient clipping for opacities.
+    #[config(default = "None")]
+    pub clipping_opacities: Option<GradientClipping>,
+    /// Gradient clipping for positions.
+    #[config(default = "None")]
+    pub clipping_positions: Option<GradientClipping>,
+    /// Gradient clipping for rotations.
+    #[config(default = "None")]
+    pub clipping_rotations: Option<GradientClipping>,
+    /// Gradient clipping for scalings.
+    #[config(default = "None")]
+    pub clipping_scalings: Option<GradientClipping>,
+    /// Exponential moving average configuration of the scene parameters.
+    #[config(default = "Default::default()")]
+    pub ema: SceneEmaConfig,
     /// Learning rate for colors SH.
     #[config(default = "1e-3.into()")]
     pub learning_rate_colors_sh: LearningRateConfig,
@@ -31,12 +52,36 @@ pub struct Gaussian3dTrainerConfig {
         default = "Gaussian3dRenderOptions::default().with_colors_sh_degree_max(0)"
     )]
     pub options_renderer: Gaussian3dRenderOptions,
+    /// Policy on non-finite values (NaN or Inf) in the loss or gradients.
+    ///
+    /// The check is disabled by default, since it synchronizes the device.
+    #[config(default = "NonFinitePolicy::Ignore")]
+    pub policy_non_finite: NonFinitePolicy,
+    /// Camera pose refiner configuration.
+    #[config(default = "Default::default()")]
+    pub pose_refiner: PoseRefinerConfig,
     /// Range for metric optimization (fine).
     #[config(default = "RangeOptions::default_with_step(2)")]
     pub range_metric_optimization_fine: RangeOptions,
+    /// Range for taking snapshots to roll back to.
+    ///
+    /// It is used only if the policy on non-finite values is to roll back.
+    #[config(default = "RangeOptions::new(1, u64::MAX, 500)")]
+    pub range_snapshot: RangeOptions,
+    /// Range for reading the loss into the statistics.
+    ///
+    /// Reading the loss synchronizes the device, so it is sparse by default.
+    #[config(default = "RangeOptions::default_with_step(100)")]
+    pub range_statistics_loss: RangeOptions,
     /// Refiner configuration.
     #[config(default = "Default::default()")]
     pub refiner: RefinerConfig,
+    /// Seed for the random number generators.
+    #[config(default = "SEED")]
+    pub seed: u64,
+    /// Resolution schedule.
+    #[config(default = "Default::default()")]
+    pub schedule_resolution: ResolutionSchedule,
 }
 
 impl Gaussian3dTrainerConfig {
@@ -45,9 +90,19 @@ impl Gaussian3dTrainerConfig {
         &self,
         device: &AB::Device,
     ) -> Gaussian3dTrainer<AB> {
-        AB::seed(SEED);
+        AB::seed(self.seed);
 
         Gaussian3dTrainer {
+            appearance: self.appearance.init(self.optimizer_adam),
+            cache_resolution: Default::default(),
+            camera_ids_queue: Default::default(),
+            clipping_colors_sh: self.clipping_colors_sh,
+            clipping_opacities: self.clipping_opacities,
+            clipping_positions: self.clipping_positions,
+            clipping_rotations: self.clipping_rotations,
+            clipping_scalings: self.clipping_scalings,
+            ema: self.ema.init(),
+            hooks: Default::default(),
             iteration: 0,
             learning_rate_colors_sh: self.learning_rate_colors_sh.init(),
             learning_rate_opacities: self.learning_rate_opacities.init(),
@@ -56,14 +111,23 @@ impl Gaussian3dTrainerConfig {
             learning_rate_scalings: self.learning_rate_scalings.init(),
             metric_optimization_coarse: metric::MeanAbsoluteError::init(),
             metric_optimization_fine: metric::MeanStructuralDissimilarity::init(device),
+            non_finite_events: Default::default(),
             optimizer_colors_sh: self.optimizer_adam.init(),
             optimizer_opacities: self.optimizer_adam.init(),
             optimizer_positions: self.optimizer_adam.init(),
             optimizer_rotations: self.optimizer_adam.init(),
             optimizer_scalings: self.optimizer_adam.init(),
             options_renderer: self.options_renderer,
+            policy_non_finite: self.policy_non_finite,
+            pose_refiner: self.pose_refiner.init(self.optimizer_adam),
             range_metric_optimization_fine: self.range_metric_optimization_fine,
+            range_snapshot: self.range_snapshot,
+            range_statistics_loss: self.range_statistics_loss,
             refiner: self.refiner.init(),
+            rng: TrainerRng::seed_from_u64(self.seed),
+            schedule_resolution: self.schedule_resolution,
+            snapshot: None,
+            statistics: Default::default(),
         }
     }
 }
@@ -0,0 +1,319 @@
+//! 3DGS scene exponential moving average (EMA).
+
+pub use super::*;
+
+/// Exponential moving average (EMA) of the scene parameters.
+///
+/// The average is updated after each optimization step.
+/// It is remapped after each densification,
+/// where the new points start from their parameters.
+#[derive(Clone, Debug)]
+pub struct SceneEma<B: Backend> {
+    /// Configuration.
+    pub config: SceneEmaConfig,
+    /// Record.
+    pub record: SceneEmaRecord<B>,
+}
+
+/// Configuration for the scene EMA.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub struct SceneEmaConfig {
+    /// Decay of the average.
+    ///
+    /// The average is updated as `decay * average + (1 - decay) * parameter`.
+    #[config(default = "0.999")]
+    pub decay: f64,
+    /// Whether to maintain the average.
+    #[config(default = "false")]
+    pub is_enabled: bool,
+}
+
+/// Record for the scene EMA.
+pub type SceneEmaRecord<B> = Option<SceneEmaState<B>>;
+
+/// State for the scene EMA.
+///
+/// The parameters are inner, e.g., the opacities are before the sigmoid.
+#[derive(Clone, Debug, Record)]
+pub struct SceneEmaState<B: Backend> {
+    /// Average of the inner colors SH.
+    pub colors_sh: Tensor<B, 2>,
+    /// Average of the inner opacities.
+    pub opacities: Tensor<B, 2>,
+    /// Average of the inner positions.
+    pub positions: Tensor<B, 2>,
+    /// Average of the inner rotations.
+    pub rotations: Tensor<B, 2>,
+    /// Average of the inner scalings.
+    pub scalings: Tensor<B, 2>,
+}
+
+impl SceneEmaConfig {
+    /// Initialize the scene EMA.
+    #[inline]
+    pub fn init<B: Backend>(self) -> SceneEma<B> {
+        SceneEma {
+            config: self,
+            record: None,
+        }
+    }
+}
+
+impl<B: Backend> SceneEma<B> {
+    /// Update the average with the parameters of the scene.
+    ///
+    /// The average starts from the parameters at the first update.
+    #[inline]
+    pub fn update<AB: AutodiffBackend<InnerBackend = B>>(
+        &mut self,
+        scene: &Gaussian3dScene<AB>,
+    ) -> &mut Self {
+        self.update_points(get_inner_points(scene))
+    }
+
+    /// Update the average with the inner parameters.
+    ///
+    /// The order is colors SH, opacities, positions, rotations and scalings.
+    pub fn update_points(
+        &mut self,
+        points: [Tensor<B, 2>; 5],
+    ) -> &mut Self {
+        if !self.config.is_enabled {
+            return self;
+        }
+
+        let decay = self.config.decay;
+
+        self.record = Some(match self.record.take() {
+            Some(state) => {
+                let mut averages = state.into_points().into_iter().zip(points);
+                let mut average = || {
+                    let (value, point) = averages.next().expect("5 parameters");
+                    value.mul_scalar(decay).add(point.mul_scalar(1.0 - decay))
+                };
+                SceneEmaState {
+                    colors_sh: average(),
+                    opacities: average(),
+                    positions: average(),
+                    rotations: average(),
+                    scalings: average(),
+                }
+            },
+            None => SceneEmaState::from_points(points),
+        });
+
+        self
+    }
+
+    /// Remap the average after the densification of the scene.
+    ///
+    /// The points in `args_to_retain` come first in the scene,
+    /// and the new points after them start from their parameters.
+    #[inline]
+    pub fn retain<AB: AutodiffBackend<InnerBackend = B>>(
+        &mut self,
+        args_to_retain: Tensor<B, 1, Int>,
+        scene: &Gaussian3dScene<AB>,
+    ) -> &mut Self {
+        self.retain_points(args_to_retain, get_inner_points(scene))
+    }
+
+    /// Remap the average after the densification with the inner parameters.
+    ///
+    /// See [`SceneEma::retain`] and [`SceneEma::update_points`] for the details.
+    pub fn retain_points(
+        &mut self,
+        args_to_retain: Tensor<B, 1, Int>,
+        points: [Tensor<B, 2>; 5],
+    ) -> &mut Self {
+        let Some(state) = self.record.take() else {
+            return self;
+        };
+
+        let point_count_retained = args_to_retain.dims()[0];
+        let points = state
+            .into_points()
+            .into_iter()
+            .zip(points)
+            .map(|(average, point)| {
+                let point_count = point.dims()[0];
+                Tensor::cat(
+                    vec![
+                        average.select(0, args_to_retain.to_owned()),
+                        point.slice([point_count_retained..point_count]),
+                    ],
+                    0,
+                )
+            })
+            .collect::<Vec<_>>()
+            .try_into()
+            .expect("5 parameters");
+        self.record = Some(SceneEmaState::from_points(points));
+
+        self
+    }
+
+    /// Transfer the scene EMA to the device.
+    pub fn to_device(
+        mut self,
+        device: &B::Device,
+    ) -> Self {
+        self.record = self.record.map(|state| {
+            SceneEmaState::from_points(
+                state.into_points().map(|average| average.to_device(device)),
+            )
+        });
+
+        self
+    }
+
+    /// Load the record.
+    #[inline]
+    pub fn load_record(
+        &mut self,
+        record: SceneEmaRecord<B>,
+    ) -> &mut Self {
+        self.record = record;
+        self
+    }
+
+    /// Unload the record.
+    #[inline]
+    pub fn into_record(self) -> SceneEmaRecord<B> {
+        self.record
+    }
+}
+
+impl<B: Backend> SceneEmaState<B> {
+    /// The order is colors SH, opacities, positions, rotations and scalings.
+    #[inline]
+    fn from_points(points: [Tensor<B, 2>; 5]) -> Self {
+        let [colors_sh, opacities, positions, rotations, scalings] = points;
+        Self {
+            colors_sh,
+            opacities,
+            positions,
+            rotations,
+            scalings,
+        }
+    }
+
+    /// The order is colors SH, opacities, positions, rotations and scalings.
+    #[inline]
+    fn into_points(self) -> [Tensor<B, 2>; 5] {
+        [
+            self.colors_sh,
+            self.opacities,
+            self.positions,
+            self.rotations,
+            self.scalings,
+        ]
+    }
+}
+
+impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
+    /// Get the scene with the averaged parameters.
+    ///
+    /// It is usable for evaluation and export.
+    ///
+    /// ## Returns
+    ///
+    /// `None` if the EMA is disabled or not updated yet.
+    pub fn get_scene_ema(
+        &self,
+        scene: &Gaussian3dScene<AB>,
+    ) -> Option<Gaussian3dScene<AB>> {
+        let state = self.ema.record.to_owned()?;
+        let mut scene = scene.to_owned();
+
+        scene
+            .set_inner_colors_sh(Tensor::from_inner(state.colors_sh))
+            .set_inner_opacities(Tensor::from_inner(state.opacities))
+            .set_inner_positions(Tensor::from_inner(state.positions))
+            .set_inner_rotations(Tensor::from_inner(state.rotations))
+            .set_inner_scalings(Tensor::from_inner(state.scalings));
+
+        Some(scene)
+    }
+}
+
+impl<B: Backend> Default for SceneEma<B> {
+    #[inline]
+    fn default() -> Self {
+        SceneEmaConfig::default().init()
+    }
+}
+
+impl Default for SceneEmaConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The order is colors SH, opacities, positions, rotations and scalings.
+#[inline]
+fn get_inner_points<AB: AutodiffBackend>(
+    scene: &Gaussian3dScene<AB>
+) -> [Tensor<AB::InnerBackend, 2>; 5] {
+    [
+        scene.colors_sh.val().inner(),
+        scene.opacities.val().inner(),
+        scene.positions.val().inner(),
+        scene.rotations.val().inner(),
+        scene.scalings.val().inner(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn update_and_retain() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let points = |value: f64| {
+            [48, 1, 3, 4, 3].map(|feature_count| {
+                Tensor::<NdArray, 2>::full([2, feature_count], value, &device)
+            })
+        };
+
+        let mut ema = SceneEmaConfig::default()
+            .with_decay(0.75)
+            .with_is_enabled(true)
+            .init::<NdArray>();
+        ema.update_points(points(0.0)).update_points(points(4.0));
+
+        let target = Tensor::<NdArray, 2>::full([2, 3], 1.0, &device).into_data();
+        let output = ema.record.to_owned().unwrap().positions.into_data();
+        output.assert_approx_eq(&target, 6);
+
+        // Retaining the second point and appending a new point
+        let args_to_retain = Tensor::<NdArray, 1, Int>::from_ints([1], &device);
+        ema.retain_points(args_to_retain, points(4.0));
+
+        let target = Tensor::<NdArray, 2>::from_floats(
+            [[1.0, 1.0, 1.0], [4.0, 4.0, 4.0]],
+            &device,
+        )
+        .into_data();
+        let output = ema.record.unwrap().positions.into_data();
+        output.assert_approx_eq(&target, 6);
+    }
+
+    #[test]
+    fn update_disabled() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let points = [48, 1, 3, 4, 3].map(|feature_count| {
+            Tensor::<NdArray, 2>::zeros([2, feature_count], &device)
+        });
+        let mut ema = SceneEmaConfig::default().init::<NdArray>();
+
+        ema.update_points(points);
+        assert!(ema.record.is_none());
+    }
+}
@@ -0,0 +1,96 @@
+//! 3DGS scene evaluation.
+
+pub use super::*;
+
+/// Evaluation of the 3DGS scene.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Gaussian3dEvaluation {
+    /// Count of cameras evaluated.
+    pub camera_count: usize,
+    /// Iteration of the trainer.
+    pub iteration: u64,
+    /// Mean absolute error averaged over the cameras.
+    pub mae: f64,
+    /// Peak signal-to-noise ratio averaged over the cameras.
+    pub psnr: f64,
+    /// Mean structural similarity averaged over the cameras.
+    pub ssim: f64,
+}
+
+impl<B: Backend> Gaussian3dTrainer<Autodiff<B>>
+where
+    Gaussian3dScene<Autodiff<B>>: Gaussian3dRenderer<B>,
+{
+    /// Evaluate the 3DGS scene at full resolution.
+    ///
+    /// The appearance compensation and the refined poses are applied if enabled.
+    pub fn evaluate(
+        &self,
+        scene: &Gaussian3dScene<Autodiff<B>>,
+        cameras: &[&sparse_view::Camera],
+    ) -> Result<Gaussian3dEvaluation, Error> {
+        let mut evaluation = Gaussian3dEvaluation {
+            camera_count: cameras.len(),
+            iteration: self.iteration,
+            ..Default::default()
+        };
+
+        if !cameras.is_empty() {
+            let mut metrics = Vec::with_capacity(cameras.len());
+            let mut metric_ssim = None;
+
+            for &camera in cameras {
+                let view = self
+                    .pose_refiner
+                    .refine_view(camera.camera_id, &camera.view);
+                let colors_rgb_2d = scene
+                    .render(&view, &self.options_renderer)?
+                    .colors_rgb_2d
+                    .detach();
+                let colors_rgb_2d = self
+                    .appearance
+                    .compensate(camera.camera_id, colors_rgb_2d)
+                    .inner();
+                let device = &colors_rgb_2d.device();
+                let colors_rgb_2d_target = camera.image.decode_rgb_tensor(device)?;
+
+                let metric_ssim = metric_ssim.get_or_insert_with(|| {
+                    metric::MeanStructuralSimilarity::<B, 3>::init(device)
+                });
+
+                metrics.push(Tensor::cat(
+                    vec![
+                        metric::MeanAbsoluteError::init().evaluate(
+                            colors_rgb_2d.to_owned(),
+                            colors_rgb_2d_target.to_owned(),
+                        ),
+                        metric::Psnr::init(device).evaluate(
+                            colors_rgb_2d.to_owned(),
+                            colors_rgb_2d_target.to_owned(),
+                        ),
+                        metric_ssim.evaluate(
+                            colors_rgb_2d.movedim(2, 0),
+                            colors_rgb_2d_target.movedim(2, 0),
+                        ),
+                    ],
+                    0,
+                ));
+            }
+
+            let metrics = Tensor::stack::<2>(metrics, 0)
+                .mean_dim(0)
+                .into_data()
+                .iter::<f64>()
+                .collect::<Vec<_>>();
+
+            evaluation.mae = metrics[0];
+            evaluation.psnr = metrics[1];
+            evaluation.ssim = metrics[2];
+        }
+
+        self.hooks
+            .invoke(|hook| hook.on_evaluation(self, scene, &evaluation));
+
+        Ok(evaluation)
+    }
+}
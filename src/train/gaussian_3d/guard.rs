@@ -0,0 +1,206 @@
+//! 3DGS non-finite value guard implementation.
+
+pub use super::*;
+pub use burn::tensor::Bool;
+
+use std::fmt;
+
+/// Policy on non-finite values (NaN or Inf) in the loss or gradients.
+///
+/// The policies other than [`NonFinitePolicy::Ignore`] check the values
+/// at every iteration, which synchronizes the device with the host.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub enum NonFinitePolicy {
+    /// Do not check the values. It is the default.
+    Ignore,
+    /// Skip the step.
+    Skip,
+    /// Skip the step and roll back to the last snapshot.
+    Rollback,
+    /// Fail with [`Error::NonFiniteValue`].
+    Abort,
+}
+
+/// Event of non-finite values detected in a step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NonFiniteEvent {
+    /// Camera IDs of the step.
+    pub camera_ids: Vec<u32>,
+    /// Whether the trainer was rolled back.
+    pub is_rolled_back: bool,
+    /// Iteration of the step.
+    pub iteration: u64,
+}
+
+/// In-memory snapshot of the scene and the trainer.
+#[derive(Clone)]
+pub struct Snapshot<AB: AutodiffBackend> {
+    /// Trainer record.
+    pub record: Gaussian3dTrainerRecord<AB::InnerBackend>,
+    /// Scene.
+    pub scene: Gaussian3dScene<AB>,
+}
+
+impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
+    /// Check if the loss and the gradients of the scene are finite.
+    pub fn is_finite(
+        &self,
+        scene: &Gaussian3dScene<AB>,
+        loss: Tensor<AB, 1>,
+        grads: &AB::Gradients,
+    ) -> bool {
+        let are_non_finite = [
+            scene.colors_sh.grad(grads),
+            scene.opacities.grad(grads),
+            scene.positions.grad(grads),
+            scene.rotations.grad(grads),
+            scene.scalings.grad(grads),
+        ]
+        .into_iter()
+        .flatten()
+        .map(is_non_finite)
+        .chain([is_non_finite(loss.inner())])
+        .collect::<Vec<_>>();
+
+        !Tensor::cat(are_non_finite, 0).any().into_scalar()
+    }
+
+    /// Take a snapshot of the scene and the trainer.
+    pub fn take_snapshot(
+        &mut self,
+        scene: &Gaussian3dScene<AB>,
+    ) -> &mut Self {
+        self.snapshot = Some(Snapshot {
+            record: self.to_record(),
+            scene: scene.to_owned(),
+        });
+
+        #[cfg(all(debug_assertions, not(test)))]
+        log::debug!(
+            target: "gausplat::trainer::gaussian_3d::guard",
+            "take_snapshot ({})",
+            self.iteration,
+        );
+
+        self
+    }
+
+    /// Roll back the scene and the trainer to the last snapshot.
+    ///
+    /// ## Returns
+    ///
+    /// `true` if there is a snapshot.
+    pub fn rollback(
+        &mut self,
+        scene: &mut Gaussian3dScene<AB>,
+    ) -> bool {
+        let Some(snapshot) = self.snapshot.to_owned() else {
+            return false;
+        };
+
+        *scene = snapshot.scene;
+        self.load_record(snapshot.record);
+
+        #[cfg(all(debug_assertions, not(test)))]
+        log::debug!(
+            target: "gausplat::trainer::gaussian_3d::guard",
+            "rollback ({})",
+            self.iteration,
+        );
+
+        true
+    }
+
+    /// Handle the non-finite values detected in a step.
+    ///
+    /// It is called after the step is skipped.
+    pub fn handle_non_finite(
+        &mut self,
+        scene: &mut Gaussian3dScene<AB>,
+        camera_ids: Vec<u32>,
+    ) -> Result<&mut Self, Error> {
+        let iteration = self.iteration;
+
+        #[cfg(all(debug_assertions, not(test)))]
+        log::debug!(
+            target: "gausplat::trainer::gaussian_3d::guard",
+            "non_finite ({}) > camera_ids ({:?})",
+            iteration,
+            camera_ids,
+        );
+
+        let is_rolled_back = match self.policy_non_finite {
+            NonFinitePolicy::Abort => {
+                return Err(Error::NonFiniteValue(iteration, camera_ids));
+            },
+            NonFinitePolicy::Rollback => self.rollback(scene),
+            NonFinitePolicy::Ignore | NonFinitePolicy::Skip => false,
+        };
+
+        self.non_finite_events.push(NonFiniteEvent {
+            camera_ids,
+            is_rolled_back,
+            iteration,
+        });
+
+        Ok(self)
+    }
+}
+
+/// Check if any value is non-finite (NaN or Inf).
+///
+/// ## Returns
+///
+/// A boolean tensor with shape `[1]`.
+pub fn is_non_finite<B: Backend, const D: usize>(
+    value: Tensor<B, D>
+) -> Tensor<B, 1, Bool> {
+    // NOTE: `x - x` is NaN if and only if `x` is NaN or Inf.
+    value.to_owned().sub(value).is_nan().any()
+}
+
+impl<AB: AutodiffBackend> fmt::Debug for Snapshot<AB> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("record.iteration", &self.record.iteration)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for NonFinitePolicy {
+    #[inline]
+    fn default() -> Self {
+        Self::Ignore
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn is_non_finite() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+
+        let value =
+            Tensor::<NdArray, 2>::from_floats([[1.0, -2.0], [0.0, 3e38]], &device);
+        let output = super::is_non_finite(value).into_scalar();
+        assert!(!output);
+
+        let value = Tensor::<NdArray, 1>::from_floats([1.0, f32::INFINITY], &device);
+        let output = super::is_non_finite(value).into_scalar();
+        assert!(output);
+
+        let value = Tensor::<NdArray, 1>::from_floats([f32::NEG_INFINITY], &device);
+        let output = super::is_non_finite(value).into_scalar();
+        assert!(output);
+
+        let value = Tensor::<NdArray, 1>::from_floats([0.0, f32::NAN], &device);
+        let output = super::is_non_finite(value).into_scalar();
+        assert!(output);
+    }
+}
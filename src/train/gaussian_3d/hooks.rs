@@ -0,0 +1,159 @@
+//! 3DGS trainer hooks.
+
+pub use super::*;
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// Hook for training events.
+///
+/// All the methods do nothing by default.
+/// The trainer invokes them with read access to itself and the scene.
+pub trait Gaussian3dTrainerHook<AB: AutodiffBackend>: Send {
+    /// It is invoked at the end of each training iteration,
+    /// including the iterations skipped for non-finite values.
+    ///
+    /// See [`Gaussian3dTrainer::statistics`] for the statistics of the iteration.
+    fn on_iteration_end(
+        &mut self,
+        _trainer: &Gaussian3dTrainer<AB>,
+        _scene: &Gaussian3dScene<AB>,
+    ) {
+    }
+
+    /// It is invoked after the scene is densified.
+    fn on_densification(
+        &mut self,
+        _trainer: &Gaussian3dTrainer<AB>,
+        _scene: &Gaussian3dScene<AB>,
+        _densification: &DensificationStatistics,
+    ) {
+    }
+
+    /// It is invoked after the max degree of colors SH is increased.
+    fn on_sh_degree_increase(
+        &mut self,
+        _trainer: &Gaussian3dTrainer<AB>,
+        _scene: &Gaussian3dScene<AB>,
+        _colors_sh_degree_max: u32,
+    ) {
+    }
+
+    /// It is invoked after the record is unloaded for a checkpoint.
+    fn on_checkpoint(
+        &mut self,
+        _trainer: &Gaussian3dTrainer<AB>,
+        _scene: &Gaussian3dScene<AB>,
+        _record: &Gaussian3dTrainerRecord<AB::InnerBackend>,
+    ) {
+    }
+
+    /// It is invoked after the scene is evaluated.
+    fn on_evaluation(
+        &mut self,
+        _trainer: &Gaussian3dTrainer<AB>,
+        _scene: &Gaussian3dScene<AB>,
+        _evaluation: &Gaussian3dEvaluation,
+    ) {
+    }
+}
+
+/// Shared hooks of the trainer.
+pub struct Gaussian3dTrainerHooks<AB: AutodiffBackend> {
+    /// Hooks in the order of invocation.
+    pub hooks: Vec<Arc<Mutex<dyn Gaussian3dTrainerHook<AB>>>>,
+}
+
+impl<AB: AutodiffBackend> Gaussian3dTrainerHooks<AB> {
+    /// Add a hook.
+    ///
+    /// ## Returns
+    ///
+    /// The shared hook, which can be inspected after training.
+    pub fn push<H: Gaussian3dTrainerHook<AB> + 'static>(
+        &mut self,
+        hook: H,
+    ) -> Arc<Mutex<H>> {
+        let hook = Arc::new(Mutex::new(hook));
+        self.hooks.push(hook.to_owned());
+        hook
+    }
+
+    /// Invoke the function on each hook.
+    pub fn invoke<F: FnMut(&mut dyn Gaussian3dTrainerHook<AB>)>(
+        &self,
+        mut f: F,
+    ) {
+        self.hooks.iter().for_each(|hook| {
+            f(&mut *hook.lock().unwrap_or_else(PoisonError::into_inner));
+        });
+    }
+
+    /// Return `true` if there is no hook.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+}
+
+impl<AB: AutodiffBackend> Clone for Gaussian3dTrainerHooks<AB> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            hooks: self.hooks.to_owned(),
+        }
+    }
+}
+
+impl<AB: AutodiffBackend> fmt::Debug for Gaussian3dTrainerHooks<AB> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("Gaussian3dTrainerHooks")
+            .field("hooks.len()", &self.hooks.len())
+            .finish()
+    }
+}
+
+impl<AB: AutodiffBackend> Default for Gaussian3dTrainerHooks<AB> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            hooks: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn invoke() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        struct Nothing;
+
+        impl<AB: AutodiffBackend> Gaussian3dTrainerHook<AB> for Nothing {}
+
+        let mut hooks = Gaussian3dTrainerHooks::<Autodiff<NdArray>>::default();
+        assert!(hooks.is_empty());
+
+        hooks.push(Nothing);
+        let hook = hooks.push(Nothing);
+        assert!(!hooks.is_empty());
+
+        let mut count = 0;
+        hooks.invoke(|_| count += 1);
+        assert_eq!(count, 2);
+
+        // The hooks are shared by the clones.
+        let hooks_cloned = hooks.to_owned();
+        let target = 3;
+        let output = Arc::strong_count(&hook);
+        assert_eq!(output, target);
+        assert_eq!(hooks_cloned.hooks.len(), 2);
+    }
+}
@@ -1,16 +1,33 @@
 //! 3DGS trainer.
 
+pub mod appearance;
 pub mod config;
+pub mod ema;
+pub mod evaluate;
+pub mod guard;
+pub mod hooks;
+pub mod pose;
+pub mod preview;
+pub mod random;
 pub mod refine;
+pub mod resolution;
+pub mod run;
+pub mod sink;
+pub mod statistics;
 
 pub use crate::{
     dataset::{sparse_view, SparseViewDataset},
     error::Error,
     metric::{self, Metric},
-    optimize::{Adam, AdamRecord, LearningRate, LearningRateRecord},
+    optimize::{
+        clip_grad, Adam, AdamRecord, GradientClipping, LearningRate, LearningRateRecord,
+    },
 };
+pub use appearance::*;
 pub use burn::{config::Config, record::Record, tensor::Tensor};
 pub use config::*;
+pub use ema::*;
+pub use evaluate::*;
 pub use gausplat_renderer::scene::gaussian_3d::{
     backend::{self, *},
     render::{
@@ -18,11 +35,42 @@ pub use gausplat_renderer::scene::gaussian_3d::{
     },
     AutodiffModule, Gaussian3dScene, SEED,
 };
+pub use guard::*;
+pub use hooks::*;
+pub use pose::*;
+pub use preview::*;
+pub use random::*;
 pub use refine::*;
+pub use resolution::*;
+pub use run::*;
+pub use sink::*;
+pub use statistics::*;
+
+use std::time::Instant;
 
 /// Trainer for 3DGS.
 #[derive(Clone, Debug)]
 pub struct Gaussian3dTrainer<AB: AutodiffBackend> {
+    /// Current appearance compensator.
+    pub appearance: Appearance<AB>,
+    /// Cache of the downsampled cameras.
+    pub cache_resolution: ResolutionCache,
+    /// Queue of the camera IDs remaining in the current epoch.
+    pub camera_ids_queue: Vec<u32>,
+    /// Gradient clipping for colors SH.
+    pub clipping_colors_sh: Option<GradientClipping>,
+    /// Gradient clipping for opacities.
+    pub clipping_opacities: Option<GradientClipping>,
+    /// Gradient clipping for positions.
+    pub clipping_positions: Option<GradientClipping>,
+    /// Gradient clipping for rotations.
+    pub clipping_rotations: Option<GradientClipping>,
+    /// Gradient clipping for scalings.
+    pub clipping_scalings: Option<GradientClipping>,
+    /// Current exponential moving average of the scene parameters.
+    pub ema: SceneEma<AB::InnerBackend>,
+    /// Hooks for training events.
+    pub hooks: Gaussian3dTrainerHooks<AB>,
     /// Current iteration.
     pub iteration: u64,
     /// Current learning rate for colors SH.
@@ -39,6 +87,8 @@ pub struct Gaussian3dTrainer<AB: AutodiffBackend> {
     pub metric_optimization_coarse: metric::MeanAbsoluteError,
     /// Metric for optimization (fine).
     pub metric_optimization_fine: metric::MeanStructuralDissimilarity<AB, 3>,
+    /// Events of non-finite values detected in training.
+    pub non_finite_events: Vec<NonFiniteEvent>,
     /// Current optimizer for colors SH.
     pub optimizer_colors_sh: Adam<AB, 2>,
     /// Current optimizer for opacities.
@@ -51,15 +101,37 @@ pub struct Gaussian3dTrainer<AB: AutodiffBackend> {
     pub optimizer_scalings: Adam<AB, 2>,
     /// Current renderer options.
     pub options_renderer: Gaussian3dRenderOptions,
+    /// Policy on non-finite values.
+    pub policy_non_finite: NonFinitePolicy,
+    /// Current camera pose refiner.
+    pub pose_refiner: PoseRefiner<AB>,
     /// Current refiner.
     pub range_metric_optimization_fine: RangeOptions,
+    /// Range for taking snapshots.
+    pub range_snapshot: RangeOptions,
+    /// Range for reading the loss into the statistics.
+    pub range_statistics_loss: RangeOptions,
     /// Current refiner.
     pub refiner: Refiner<AB::InnerBackend>,
+    /// Current random number generator.
+    pub rng: TrainerRng,
+    /// Resolution schedule.
+    pub schedule_resolution: ResolutionSchedule,
+    /// Last snapshot for rollback.
+    pub snapshot: Option<Snapshot<AB>>,
+    /// Statistics of the last iteration.
+    pub statistics: Gaussian3dTrainerStatistics,
 }
 
 /// Trainer record for 3DGS.
 #[derive(Clone, Debug, Record)]
 pub struct Gaussian3dTrainerRecord<B: Backend> {
+    /// Appearance compensator.
+    pub appearance: AppearanceRecord<B>,
+    /// Queue of the camera IDs remaining in the current epoch.
+    pub camera_ids_queue: Vec<u32>,
+    /// Exponential moving average of the scene parameters.
+    pub ema: SceneEmaRecord<B>,
     /// Iteration.
     pub iteration: u64,
     /// Learning rate for colors SH.
@@ -84,8 +156,12 @@ pub struct Gaussian3dTrainerRecord<B: Backend> {
     pub optimizer_scalings: AdamRecord<B, 2>,
     /// Renderer options.
     pub options_renderer: Gaussian3dRenderOptions,
+    /// Camera pose refiner.
+    pub pose_refiner: PoseRefinerRecord<B>,
     /// Refiner.
     pub refiner: RefinerRecord<B>,
+    /// State of the random number generator.
+    pub rng_state: u64,
 }
 
 impl<B: Backend> Gaussian3dTrainer<Autodiff<B>>
@@ -93,35 +169,176 @@ where
     Gaussian3dScene<Autodiff<B>>: Gaussian3dRenderer<B>,
 {
     /// Train the 3DGS scene.
+    #[inline]
     pub fn train(
         &mut self,
         scene: &mut Gaussian3dScene<Autodiff<B>>,
         camera: &sparse_view::Camera,
     ) -> Result<&mut Self, Error> {
+        self.train_batch(scene, &[camera])
+    }
+
+    /// Train the 3DGS scene with a batch of cameras.
+    ///
+    /// The losses of the cameras are averaged,
+    /// so the scene is optimized once with the averaged gradients.
+    ///
+    /// ## Details
+    ///
+    /// The camera poses are refined only if the batch has a single camera,
+    /// since the gradients of the positions cannot be separated by camera.
+    pub fn train_batch(
+        &mut self,
+        scene: &mut Gaussian3dScene<Autodiff<B>>,
+        cameras: &[&sparse_view::Camera],
+    ) -> Result<&mut Self, Error> {
+        if cameras.is_empty() {
+            return Ok(self);
+        }
+
+        let time_start = Instant::now();
+        let colors_sh_degree_max = self.options_renderer.colors_sh_degree_max;
         self.iteration += 1;
 
         #[cfg(all(debug_assertions, not(test)))]
         log::debug!(
             target: "gausplat::trainer::gaussian_3d::train",
-            "iteration ({})",
+            "iteration ({}) > camera_count ({})",
             self.iteration,
+            cameras.len(),
         );
 
-        let output = scene.render(&camera.view, &self.options_renderer)?;
+        let mut losses = Vec::with_capacity(cameras.len());
+        let mut outputs = Vec::with_capacity(cameras.len());
+        let mut transforms = Vec::with_capacity(cameras.len());
+
+        // Downsampling the cameras in early iterations
+        self.cache_resolution.update(
+            &self.schedule_resolution,
+            self.iteration,
+            cameras,
+        )?;
 
-        let colors_rgb_2d_target = camera
-            .image
-            .decode_rgb_tensor(&output.colors_rgb_2d.device())?
-            .set_require_grad(false);
+        for &camera in cameras {
+            let camera = self.cache_resolution.get(camera);
 
-        let loss = self.get_loss_colors_rgb_2d(
-            output.colors_rgb_2d.to_owned(),
-            colors_rgb_2d_target.to_owned(),
-        );
+            let view = self
+                .pose_refiner
+                .refine_view(camera.camera_id, &camera.view);
+            let output = scene.render(&view, &self.options_renderer)?;
+
+            let colors_rgb_2d_target = camera
+                .image
+                .decode_rgb_tensor(&output.colors_rgb_2d.device())?
+                .set_require_grad(false);
 
+            // Compensating the appearance of the camera
+            let transform = self.appearance.config.is_enabled.then(|| {
+                self.appearance
+                    .get_transform(camera.camera_id, &colors_rgb_2d_target.device())
+            });
+            let colors_rgb_2d = match &transform {
+                Some(transform) => Appearance::apply(
+                    output.colors_rgb_2d.to_owned(),
+                    transform.to_owned(),
+                ),
+                None => output.colors_rgb_2d.to_owned(),
+            };
+
+            let loss = self
+                .get_loss_colors_rgb_2d(colors_rgb_2d, colors_rgb_2d_target.to_owned());
+
+            losses.push(loss);
+            outputs.push(output);
+            transforms.push((camera.camera_id, transform));
+        }
+
+        let loss = Tensor::cat(losses, 0).mean();
         let grads = &mut loss.backward();
 
-        Ok(self.optimize(scene, grads).refine(scene, grads, output))
+        // Reading the loss for statistics only in the range
+        let loss_value = self.range_statistics_loss.has(self.iteration).then(|| {
+            loss.to_owned()
+                .into_data()
+                .iter::<f64>()
+                .next()
+                .unwrap_or_default()
+        });
+        self.statistics = Gaussian3dTrainerStatistics {
+            camera_count: cameras.len(),
+            colors_sh_degree_max: self.options_renderer.colors_sh_degree_max,
+            iteration: self.iteration,
+            loss: loss_value,
+            point_count: scene.positions.val().dims()[0],
+            ..Default::default()
+        }
+        .with_learning_rates(self);
+
+        // Checking the non-finite values before the parameters are updated
+        if self.policy_non_finite != NonFinitePolicy::Ignore
+            && !self.is_finite(scene, loss, grads)
+        {
+            let camera_ids = cameras.iter().map(|camera| camera.camera_id).collect();
+            self.statistics.duration = time_start.elapsed();
+            self.handle_non_finite(scene, camera_ids)?;
+            self.hooks.invoke(|hook| hook.on_iteration_end(self, scene));
+            return Ok(self);
+        }
+
+        for (camera_id, transform) in transforms {
+            if let Some(transform) = transform {
+                self.appearance.optimize(camera_id, transform, grads);
+            }
+        }
+
+        // Refining the camera pose before the positions are optimized
+        let config = &self.pose_refiner.config;
+        if let [camera] = cameras {
+            if config.is_enabled && config.range_refinement.has(self.iteration) {
+                if let Some(positions_grad) = scene.positions.grad(grads) {
+                    self.pose_refiner.optimize(
+                        camera.camera_id,
+                        scene.positions.val().inner(),
+                        positions_grad,
+                    );
+                }
+            }
+        }
+
+        self.optimize(scene, grads)
+            .refine_batch(scene, grads, outputs)
+            .ema
+            .update(scene);
+
+        self.statistics.colors_sh_degree_max = self.options_renderer.colors_sh_degree_max;
+        self.statistics.point_count = scene.positions.val().dims()[0];
+
+        if self.policy_non_finite == NonFinitePolicy::Rollback
+            && self.range_snapshot.has(self.iteration)
+        {
+            self.take_snapshot(scene);
+        }
+
+        self.statistics.duration = time_start.elapsed();
+
+        // Invoking the hooks
+
+        if let Some(densification) = &self.statistics.densification {
+            self.hooks
+                .invoke(|hook| hook.on_densification(self, scene, densification));
+        }
+        if self.options_renderer.colors_sh_degree_max != colors_sh_degree_max {
+            self.hooks.invoke(|hook| {
+                hook.on_sh_degree_increase(
+                    self,
+                    scene,
+                    self.options_renderer.colors_sh_degree_max,
+                )
+            });
+        }
+        self.hooks.invoke(|hook| hook.on_iteration_end(self, scene));
+
+        Ok(self)
     }
 }
 
@@ -160,6 +377,7 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
         // Updating the parameters using the gradients
 
         if let Some(grad) = scene.colors_sh.grad_remove(grads) {
+            let grad = clip_grad(self.clipping_colors_sh, grad);
             scene.set_inner_colors_sh(self.optimizer_colors_sh.update(
                 *self.learning_rate_colors_sh,
                 scene.colors_sh.val(),
@@ -167,6 +385,7 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
             ));
         }
         if let Some(grad) = scene.opacities.grad_remove(grads) {
+            let grad = clip_grad(self.clipping_opacities, grad);
             scene.set_inner_opacities(self.optimizer_opacities.update(
                 *self.learning_rate_opacities,
                 scene.opacities.val(),
@@ -174,6 +393,7 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
             ));
         }
         if let Some(grad) = scene.positions.grad_remove(grads) {
+            let grad = clip_grad(self.clipping_positions, grad);
             scene.set_inner_positions(self.optimizer_positions.update(
                 *self.learning_rate_positions,
                 scene.positions.val(),
@@ -181,6 +401,7 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
             ));
         }
         if let Some(grad) = scene.rotations.grad_remove(grads) {
+            let grad = clip_grad(self.clipping_rotations, grad);
             scene.set_inner_rotations(self.optimizer_rotations.update(
                 *self.learning_rate_rotations,
                 scene.rotations.val(),
@@ -188,6 +409,7 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
             ));
         }
         if let Some(grad) = scene.scalings.grad_remove(grads) {
+            let grad = clip_grad(self.clipping_scalings, grad);
             scene.set_inner_scalings(self.optimizer_scalings.update(
                 *self.learning_rate_scalings,
                 scene.scalings.val(),
@@ -202,6 +424,9 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
         self.learning_rate_positions.update();
         self.learning_rate_rotations.update();
         self.learning_rate_scalings.update();
+        if self.appearance.config.is_enabled {
+            self.appearance.learning_rate.update();
+        }
 
         self
     }
@@ -213,11 +438,14 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
         mut self,
         device: &AB::Device,
     ) -> Self {
+        self.appearance = self.appearance.to_device(device);
+        self.ema = self.ema.to_device(device);
         self.optimizer_colors_sh = self.optimizer_colors_sh.to_device(device);
         self.optimizer_opacities = self.optimizer_opacities.to_device(device);
         self.optimizer_positions = self.optimizer_positions.to_device(device);
         self.optimizer_rotations = self.optimizer_rotations.to_device(device);
         self.optimizer_scalings = self.optimizer_scalings.to_device(device);
+        self.pose_refiner = self.pose_refiner.to_device(device);
         self.refiner = self.refiner.to_device(device);
 
         self
@@ -228,6 +456,9 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
         &mut self,
         record: Gaussian3dTrainerRecord<AB::InnerBackend>,
     ) -> &mut Self {
+        self.appearance.load_record(record.appearance);
+        self.camera_ids_queue = record.camera_ids_queue;
+        self.ema.load_record(record.ema);
         self.iteration = record.iteration;
         self.learning_rate_colors_sh
             .load_record(record.learning_rate_colors_sh);
@@ -250,14 +481,39 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
         self.optimizer_scalings
             .load_record(record.optimizer_scalings);
         self.options_renderer = record.options_renderer;
+        self.pose_refiner.load_record(record.pose_refiner);
         self.refiner.load_record(record.refiner);
+        self.rng.state = record.rng_state;
 
         self
     }
 
+    /// Unload the record for a checkpoint.
+    ///
+    /// The hooks are invoked with the record.
+    pub fn checkpoint(
+        &self,
+        scene: &Gaussian3dScene<AB>,
+    ) -> Gaussian3dTrainerRecord<AB::InnerBackend> {
+        let record = self.to_record();
+        self.hooks
+            .invoke(|hook| hook.on_checkpoint(self, scene, &record));
+        record
+    }
+
+    /// Unload the record without consuming the trainer.
+    pub fn to_record(&self) -> Gaussian3dTrainerRecord<AB::InnerBackend> {
+        let mut trainer = self.to_owned();
+        trainer.snapshot = None;
+        trainer.into_record()
+    }
+
     /// Unload the record.
     pub fn into_record(self) -> Gaussian3dTrainerRecord<AB::InnerBackend> {
         Gaussian3dTrainerRecord {
+            appearance: self.appearance.into_record(),
+            camera_ids_queue: self.camera_ids_queue,
+            ema: self.ema.into_record(),
             iteration: self.iteration,
             learning_rate_colors_sh: self.learning_rate_colors_sh.into_record(),
             learning_rate_opacities: self.learning_rate_opacities.into_record(),
@@ -270,7 +526,9 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
             optimizer_rotations: self.optimizer_rotations.into_record(),
             optimizer_scalings: self.optimizer_scalings.into_record(),
             options_renderer: self.options_renderer,
+            pose_refiner: self.pose_refiner.into_record(),
             refiner: self.refiner.into_record(),
+            rng_state: self.rng.state,
         }
     }
 }
@@ -289,6 +547,8 @@ mod tests {
         use super::*;
 
         Adam::<Autodiff<Wgpu>, 2>::default();
+        Appearance::<Autodiff<Wgpu>>::default();
+        PoseRefiner::<Autodiff<Wgpu>>::default();
         Gaussian3dTrainer::<Autodiff<Wgpu>>::default();
         Refiner::<Autodiff<Wgpu>>::default();
 
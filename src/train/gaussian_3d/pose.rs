@@ -0,0 +1,502 @@
+//! 3DGS camera pose refinement implementation.
+
+pub use super::*;
+
+use std::collections::BTreeMap;
+
+/// Camera pose refiner for 3DGS.
+///
+/// It learns a rigid transform `T` for each camera,
+/// which is applied to the view transform `V` as `V · T`.
+///
+/// ## Details
+///
+/// The renderer does not differentiate the view transform,
+/// so the gradient of each pose is derived from the gradients of the positions.
+/// It is a first-order approximation which ignores the orientations
+/// and the view-dependent colors of the points.
+#[derive(Clone, Debug)]
+pub struct PoseRefiner<AB: AutodiffBackend> {
+    /// Configuration.
+    pub config: PoseRefinerConfig,
+    /// Current learning rate.
+    pub learning_rate: LearningRate,
+    /// Configuration of the optimizer for each camera.
+    pub optimizer_adam: AdamConfig,
+    /// States of each camera.
+    pub states: BTreeMap<u32, PoseRefinerState<AB>>,
+}
+
+/// Configuration for the camera pose refiner.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub struct PoseRefinerConfig {
+    /// Whether to refine the camera poses in training.
+    #[config(default = "false")]
+    pub is_enabled: bool,
+    /// Learning rate.
+    #[config(default = "LearningRateConfig::new(1e-4).with_end(1e-6).with_count(30000)")]
+    pub learning_rate: LearningRateConfig,
+    /// Range for refinement.
+    ///
+    /// The poses are frozen outside the range.
+    #[config(default = "RangeOptions::new(1000, u64::MAX, 1)")]
+    pub range_refinement: RangeOptions,
+}
+
+/// State of the camera pose refiner for a camera.
+#[derive(Clone, Debug)]
+pub struct PoseRefinerState<AB: AutodiffBackend> {
+    /// Pose delta.
+    ///
+    /// `[6]`, the axis-angle rotation followed by the translation.
+    pub delta: Tensor<AB::InnerBackend, 1>,
+    /// Pose delta read back to the host.
+    ///
+    /// It is updated with [`PoseRefinerState::delta`],
+    /// so the views are refined without synchronizing the device.
+    pub delta_host: [f64; 6],
+    /// Current optimizer.
+    pub optimizer: Adam<AB, 1>,
+}
+
+/// Record for the camera pose refiner.
+#[derive(Clone, Debug, Record)]
+pub struct PoseRefinerRecord<B: Backend> {
+    /// Learning rate.
+    pub learning_rate: LearningRateRecord,
+    /// Records of each camera.
+    pub states: Vec<PoseRefinerStateRecord<B>>,
+}
+
+/// Record of the camera pose refiner for a camera.
+#[derive(Clone, Debug, Record)]
+pub struct PoseRefinerStateRecord<B: Backend> {
+    /// Camera ID.
+    pub camera_id: u32,
+    /// Pose delta.
+    pub delta: Tensor<B, 1>,
+    /// Optimizer.
+    pub optimizer: AdamRecord<B, 1>,
+}
+
+impl PoseRefinerConfig {
+    /// Initialize the camera pose refiner.
+    #[inline]
+    pub fn init<AB: AutodiffBackend>(
+        self,
+        optimizer_adam: AdamConfig,
+    ) -> PoseRefiner<AB> {
+        PoseRefiner {
+            config: self,
+            learning_rate: self.learning_rate.init(),
+            optimizer_adam,
+            states: Default::default(),
+        }
+    }
+}
+
+impl<AB: AutodiffBackend> PoseRefinerState<AB> {
+    /// Create the state, reading the pose delta back to the host.
+    #[inline]
+    pub fn new(
+        delta: Tensor<AB::InnerBackend, 1>,
+        optimizer: Adam<AB, 1>,
+    ) -> Self {
+        let mut state = Self {
+            delta: delta.to_owned(),
+            delta_host: [0.0; 6],
+            optimizer,
+        };
+        state.set_delta(delta);
+        state
+    }
+
+    /// Set the pose delta, reading it back to the host.
+    pub fn set_delta(
+        &mut self,
+        delta: Tensor<AB::InnerBackend, 1>,
+    ) -> &mut Self {
+        let mut values = delta.to_owned().into_data().iter::<f64>();
+        self.delta_host = [(); 6].map(|_| values.next().unwrap_or_default());
+        self.delta = delta;
+        self
+    }
+}
+
+impl<AB: AutodiffBackend> PoseRefiner<AB> {
+    /// Get the pose delta of the camera.
+    ///
+    /// ## Returns
+    ///
+    /// The row-major rotation matrix and the translation vector.
+    pub fn get_delta(
+        &self,
+        camera_id: u32,
+    ) -> Option<([[f64; 3]; 3], [f64; 3])> {
+        let delta = &self.states.get(&camera_id)?.delta_host;
+        let rotation = rotation_from_axis_angle(&[delta[0], delta[1], delta[2]]);
+        let translation = [delta[3], delta[4], delta[5]];
+
+        Some((rotation, translation))
+    }
+
+    /// Apply the pose delta of the camera to the view.
+    ///
+    /// It returns the view unchanged if the refiner is disabled
+    /// or the camera has no delta.
+    pub fn refine_view(
+        &self,
+        camera_id: u32,
+        view: &sparse_view::View,
+    ) -> sparse_view::View {
+        let mut view = view.to_owned();
+        if !self.config.is_enabled {
+            return view;
+        }
+        let Some((rotation, translation)) = self.get_delta(camera_id) else {
+            return view;
+        };
+
+        // V' = V · T, where both are column-major in the view.
+        let transform = [
+            [rotation[0][0], rotation[1][0], rotation[2][0], 0.0],
+            [rotation[0][1], rotation[1][1], rotation[2][1], 0.0],
+            [rotation[0][2], rotation[1][2], rotation[2][2], 0.0],
+            [translation[0], translation[1], translation[2], 1.0],
+        ];
+        let view_transform = view.view_transform;
+        view.view_transform = transform.map(|column| {
+            [0, 1, 2, 3]
+                .map(|row| (0..4).map(|k| view_transform[k][row] * column[k]).sum())
+        });
+
+        // c' = Rᵀ · (c - t)
+        let view_position = view.view_position;
+        view.view_position = [0, 1, 2].map(|i| {
+            (0..3)
+                .map(|k| rotation[k][i] * (view_position[k] - translation[k]))
+                .sum()
+        });
+
+        view
+    }
+
+    /// Apply the pose deltas to the views of the cameras.
+    ///
+    /// It is used to export the refined poses.
+    pub fn refine_cameras(
+        &self,
+        cameras: &mut sparse_view::Cameras,
+    ) -> &Self {
+        cameras.values_mut().for_each(|camera| {
+            camera.view = self.refine_view(camera.camera_id, &camera.view);
+        });
+
+        self
+    }
+
+    /// Optimize the pose delta of the camera.
+    ///
+    /// ## Arguments
+    ///
+    /// * `camera_id` - The camera ID.
+    /// * `positions` - The positions of the points with shape `[N, 3]`.
+    /// * `positions_grad` - The gradients of the positions with shape `[N, 3]`.
+    pub fn optimize(
+        &mut self,
+        camera_id: u32,
+        positions: Tensor<AB::InnerBackend, 2>,
+        positions_grad: Tensor<AB::InnerBackend, 2>,
+    ) -> &mut Self {
+        let device = &positions.device();
+        let (rotation, translation) =
+            self.get_delta(camera_id).unwrap_or((IDENTITY, [0.0; 3]));
+        let grad = get_pose_grad(positions, positions_grad, &rotation, &translation)
+            .map(|g| g as f32);
+
+        let optimizer_adam = self.optimizer_adam;
+        let state = self
+            .states
+            .entry(camera_id)
+            .or_insert_with(|| PoseRefinerState {
+                delta: Tensor::zeros([6], device),
+                delta_host: [0.0; 6],
+                optimizer: optimizer_adam.init(),
+            });
+
+        let delta = state
+            .optimizer
+            .update(
+                *self.learning_rate,
+                Tensor::from_inner(state.delta.to_owned()),
+                Tensor::from_floats(grad, device),
+            )
+            .inner();
+        state.set_delta(delta);
+        self.learning_rate.update();
+
+        self
+    }
+
+    /// Transfer the camera pose refiner to the device.
+    pub fn to_device(
+        mut self,
+        device: &AB::Device,
+    ) -> Self {
+        self.states = self
+            .states
+            .into_iter()
+            .map(|(camera_id, mut state)| {
+                state.delta = state.delta.to_device(device);
+                state.optimizer = state.optimizer.to_device(device);
+                (camera_id, state)
+            })
+            .collect();
+
+        self
+    }
+
+    /// Load the record.
+    pub fn load_record(
+        &mut self,
+        record: PoseRefinerRecord<AB::InnerBackend>,
+    ) -> &mut Self {
+        self.learning_rate.load_record(record.learning_rate);
+        self.states = record
+            .states
+            .into_iter()
+            .map(|record| {
+                let mut optimizer = self.optimizer_adam.init();
+                optimizer.load_record(record.optimizer);
+                let state = PoseRefinerState::new(record.delta, optimizer);
+                (record.camera_id, state)
+            })
+            .collect();
+
+        self
+    }
+
+    /// Unload the record.
+    pub fn into_record(self) -> PoseRefinerRecord<AB::InnerBackend> {
+        PoseRefinerRecord {
+            learning_rate: self.learning_rate.into_record(),
+            states: self
+                .states
+                .into_iter()
+                .map(|(camera_id, state)| PoseRefinerStateRecord {
+                    camera_id,
+                    delta: state.delta,
+                    optimizer: state.optimizer.into_record(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<AB: AutodiffBackend> Default for PoseRefiner<AB> {
+    #[inline]
+    fn default() -> Self {
+        PoseRefinerConfig::default().init(Default::default())
+    }
+}
+
+impl Default for PoseRefinerConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the gradient of the pose delta from the gradients of the positions.
+///
+/// The points are transformed by the pose delta as `q = R · p + t`,
+/// and the gradient is taken with respect to a left perturbation of the delta.
+///
+/// ## Returns
+///
+/// The gradient of the axis-angle rotation followed by the translation.
+pub fn get_pose_grad<B: Backend>(
+    positions: Tensor<B, 2>,
+    positions_grad: Tensor<B, 2>,
+    rotation: &[[f64; 3]; 3],
+    translation: &[f64; 3],
+) -> [f64; 6] {
+    let point_count = positions.dims()[0];
+    let roll = |value: Tensor<B, 2>, shift: usize| {
+        Tensor::cat(
+            vec![
+                value.to_owned().slice([0..point_count, shift..3]),
+                value.slice([0..point_count, 0..shift]),
+            ],
+            1,
+        )
+    };
+
+    // Σ p × g
+    let moments = roll(positions.to_owned(), 1)
+        .mul(roll(positions_grad.to_owned(), 2))
+        .sub(roll(positions, 2).mul(roll(positions_grad.to_owned(), 1)))
+        .sum_dim(0);
+    // Σ g
+    let forces = positions_grad.sum_dim(0);
+
+    let sums = Tensor::cat(vec![moments, forces], 1)
+        .into_data()
+        .iter::<f64>()
+        .collect::<Vec<_>>();
+    let rotate = |vector: [f64; 3]| {
+        rotation.map(|row| row.iter().zip(vector).map(|(r, v)| r * v).sum::<f64>())
+    };
+    let moment = rotate([sums[0], sums[1], sums[2]]);
+    let force = rotate([sums[3], sums[4], sums[5]]);
+
+    // Σ q × (R · g) = R · Σ p × g + t × R · Σ g
+    [
+        moment[0] + translation[1] * force[2] - translation[2] * force[1],
+        moment[1] + translation[2] * force[0] - translation[0] * force[2],
+        moment[2] + translation[0] * force[1] - translation[1] * force[0],
+        force[0],
+        force[1],
+        force[2],
+    ]
+}
+
+/// Convert the axis-angle rotation to the row-major rotation matrix.
+pub fn rotation_from_axis_angle(axis_angle: &[f64; 3]) -> [[f64; 3]; 3] {
+    let [x, y, z] = *axis_angle;
+    let angle = (x * x + y * y + z * z).sqrt();
+    if angle < 1e-12 {
+        return [[1.0, -z, y], [z, 1.0, -x], [-y, x, 1.0]];
+    }
+
+    let [x, y, z] = [x / angle, y / angle, z / angle];
+    let (s, c) = angle.sin_cos();
+    let d = 1.0 - c;
+
+    [
+        [c + x * x * d, x * y * d - z * s, x * z * d + y * s],
+        [y * x * d + z * s, c + y * y * d, y * z * d - x * s],
+        [z * x * d - y * s, z * y * d + x * s, c + z * z * d],
+    ]
+}
+
+const IDENTITY: [[f64; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn get_pose_grad() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let positions = Tensor::<NdArray, 2>::from_floats(
+            [[1.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, -1.0]],
+            &device,
+        );
+        let positions_grad = Tensor::<NdArray, 2>::from_floats(
+            [[0.0, 1.0, 0.0], [0.5, 0.0, 0.0], [0.0, 0.0, 3.0]],
+            &device,
+        );
+
+        // Σ p × g = [0, 0, 1] + [0, 0, -1] + [0, 0, 0]
+        // Σ g = [0.5, 1, 3]
+        let target = [0.0, 0.0, 0.0, 0.5, 1.0, 3.0];
+        let output = super::get_pose_grad(
+            positions.to_owned(),
+            positions_grad.to_owned(),
+            &IDENTITY,
+            &[0.0; 3],
+        );
+        output.iter().zip(target).for_each(|(o, t)| {
+            assert!((o - t).abs() < 1e-6, "{output:?}");
+        });
+
+        // t × Σ g = [0, 0, 1] × [0.5, 1, 3] = [-1, 0.5, 0]
+        let target = [-1.0, 0.5, 0.0, 0.5, 1.0, 3.0];
+        let output =
+            super::get_pose_grad(positions, positions_grad, &IDENTITY, &[0.0, 0.0, 1.0]);
+        output.iter().zip(target).for_each(|(o, t)| {
+            assert!((o - t).abs() < 1e-6, "{output:?}");
+        });
+    }
+
+    #[test]
+    fn refine_view() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let mut refiner = PoseRefinerConfig::default()
+            .with_is_enabled(true)
+            .init::<Autodiff<NdArray>>(Default::default());
+
+        let view = sparse_view::View {
+            view_position: [0.0, 0.0, -4.0],
+            view_transform: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 4.0, 1.0],
+            ],
+            ..Default::default()
+        };
+
+        let target = view.to_owned();
+        let output = refiner.refine_view(1, &view);
+        assert_eq!(output, target);
+
+        let delta = [0.0, 0.3, 0.0, 0.5, -0.2, 0.1_f32];
+        refiner.states.insert(
+            1,
+            PoseRefinerState::new(
+                Tensor::from_floats(delta, &device),
+                Default::default(),
+            ),
+        );
+
+        // The delta is read from the host
+        let delta = delta.map(f64::from);
+        let target_delta = Some((
+            super::rotation_from_axis_angle(&[delta[0], delta[1], delta[2]]),
+            [delta[3], delta[4], delta[5]],
+        ));
+        let output_delta = refiner.get_delta(1);
+        assert_eq!(output_delta, target_delta);
+
+        let output = refiner.refine_view(1, &view);
+
+        // The camera position should be mapped to the origin.
+        let position = output.view_position;
+        let transform = output.view_transform;
+        (0..3).for_each(|row| {
+            let value = (0..3).map(|k| transform[k][row] * position[k]).sum::<f64>()
+                + transform[3][row];
+            assert!(value.abs() < 1e-6, "{output:?}");
+        });
+
+        refiner.config.is_enabled = false;
+        let output = refiner.refine_view(1, &view);
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn rotation_from_axis_angle() {
+        use super::*;
+
+        let target = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+        let output =
+            super::rotation_from_axis_angle(&[0.0, 0.0, std::f64::consts::FRAC_PI_2]);
+        output
+            .iter()
+            .flatten()
+            .zip(target.iter().flatten())
+            .for_each(|(o, t)| {
+                assert!((o - t).abs() < 1e-12, "{output:?}");
+            });
+
+        let target = IDENTITY;
+        let output = super::rotation_from_axis_angle(&[0.0; 3]);
+        assert_eq!(output, target);
+    }
+}
@@ -0,0 +1,216 @@
+//! 3DGS training previews.
+
+pub use super::*;
+
+use image::RgbImage;
+use std::{fmt, fs, path::PathBuf};
+
+/// Hook writing the previews of the reference cameras during training.
+///
+/// Each preview is a PNG file named `{iteration:06}-{camera_id}.png`
+/// in the directory, which places the render, the target
+/// and the error map side by side.
+///
+/// The previews are also written to the metrics writer if any,
+/// in the group [`GROUP_PREVIEW`] with the camera id as the tag.
+pub struct Gaussian3dPreviewHook {
+    /// Reference cameras.
+    pub cameras: Vec<sparse_view::Camera>,
+    /// Directory of the previews.
+    pub directory: PathBuf,
+    /// Range of iterations to write the previews.
+    pub range: RangeOptions,
+    /// Metrics writer of the previews.
+    pub writer: Option<Box<dyn MetricsWriter>>,
+}
+
+impl Gaussian3dPreviewHook {
+    /// Write the previews of the cameras into the directory.
+    ///
+    /// The directory is created if it does not exist.
+    pub fn new(
+        directory: impl Into<PathBuf>,
+        cameras: Vec<sparse_view::Camera>,
+        range: RangeOptions,
+    ) -> Result<Self, Error> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+
+        Ok(Self {
+            cameras,
+            directory,
+            range,
+            writer: None,
+        })
+    }
+
+    /// Write the previews to the metrics writer as well.
+    #[inline]
+    pub fn with_writer(
+        mut self,
+        writer: impl MetricsWriter + 'static,
+    ) -> Self {
+        self.writer = Some(Box::new(writer));
+        self
+    }
+
+    /// Write the previews of the current iteration.
+    pub fn write<B: Backend>(
+        &mut self,
+        trainer: &Gaussian3dTrainer<Autodiff<B>>,
+        scene: &Gaussian3dScene<Autodiff<B>>,
+    ) -> Result<&mut Self, Error>
+    where
+        Gaussian3dScene<Autodiff<B>>: Gaussian3dRenderer<B>,
+    {
+        for camera in &self.cameras {
+            let path = self
+                .directory
+                .join(format!("{:06}-{}.png", trainer.iteration, camera.camera_id));
+            let preview = trainer.render_preview(scene, camera)?;
+            preview.save(path)?;
+
+            if let Some(writer) = &mut self.writer {
+                writer.write_image(
+                    GROUP_PREVIEW,
+                    &camera.camera_id.to_string(),
+                    trainer.iteration,
+                    &preview,
+                )?;
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+impl fmt::Debug for Gaussian3dPreviewHook {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("Gaussian3dPreviewHook")
+            .field("cameras.len()", &self.cameras.len())
+            .field("directory", &self.directory)
+            .field("range", &self.range)
+            .field("writer.is_some()", &self.writer.is_some())
+            .finish()
+    }
+}
+
+impl<B: Backend> Gaussian3dTrainerHook<Autodiff<B>> for Gaussian3dPreviewHook
+where
+    Gaussian3dScene<Autodiff<B>>: Gaussian3dRenderer<B>,
+{
+    fn on_iteration_end(
+        &mut self,
+        trainer: &Gaussian3dTrainer<Autodiff<B>>,
+        scene: &Gaussian3dScene<Autodiff<B>>,
+    ) {
+        if !self.range.has(trainer.iteration) {
+            return;
+        }
+
+        if let Err(error) = self.write(trainer, scene) {
+            log::warn!(
+                target: "gausplat::trainer::gaussian_3d::preview",
+                "Gaussian3dPreviewHook::on_iteration_end > {error}",
+            );
+        }
+    }
+}
+
+impl<B: Backend> Gaussian3dTrainer<Autodiff<B>>
+where
+    Gaussian3dScene<Autodiff<B>>: Gaussian3dRenderer<B>,
+{
+    /// Render the preview of the camera at full resolution.
+    ///
+    /// The appearance compensation and the refined pose are applied if enabled.
+    /// See [`compose_preview`] for the layout.
+    pub fn render_preview(
+        &self,
+        scene: &Gaussian3dScene<Autodiff<B>>,
+        camera: &sparse_view::Camera,
+    ) -> Result<RgbImage, Error> {
+        let view = self
+            .pose_refiner
+            .refine_view(camera.camera_id, &camera.view);
+        let colors_rgb_2d = scene
+            .render(&view, &self.options_renderer)?
+            .colors_rgb_2d
+            .detach();
+        let colors_rgb_2d = self
+            .appearance
+            .compensate(camera.camera_id, colors_rgb_2d)
+            .inner();
+        let colors_rgb_2d_target =
+            camera.image.decode_rgb_tensor(&colors_rgb_2d.device())?;
+
+        compose_preview(colors_rgb_2d, colors_rgb_2d_target)
+    }
+}
+
+/// Compose the render, the target and the error map side by side.
+///
+/// The inputs are RGB tensors `[H, W, 3]` in `[0, 1]`.
+/// The error map is the grayscale absolute error averaged over the channels.
+///
+/// ## Returns
+///
+/// The image of size `[3 * W, H]`.
+pub fn compose_preview<B: Backend>(
+    colors_rgb_2d: Tensor<B, 3>,
+    colors_rgb_2d_target: Tensor<B, 3>,
+) -> Result<RgbImage, Error> {
+    let dims = colors_rgb_2d.dims();
+    let dims_target = colors_rgb_2d_target.dims();
+    if dims != dims_target {
+        return Err(Error::MismatchedTensorShape(
+            dims.to_vec(),
+            dims_target.to_vec(),
+        ));
+    }
+    let [height, width, _] = dims;
+
+    let errors = (colors_rgb_2d.to_owned() - colors_rgb_2d_target.to_owned())
+        .abs()
+        .mean_dim(2);
+    let errors = Tensor::cat(vec![errors.to_owned(), errors.to_owned(), errors], 2);
+    let preview = Tensor::cat(vec![colors_rgb_2d, colors_rgb_2d_target, errors], 1)
+        .into_data()
+        .iter::<f32>()
+        .map(|value| (value * 255.0).round().clamp(0.0, 255.0) as u8)
+        .collect::<Vec<_>>();
+
+    RgbImage::from_raw(width as u32 * 3, height as u32, preview).ok_or_else(|| {
+        Error::MismatchedTensorShape(vec![height, width * 3, 3], vec![height, width, 3])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn compose_preview() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let device = Default::default();
+        let colors_rgb_2d =
+            Tensor::<NdArray, 3>::from_floats([[[1.0, 0.0, 0.5]]], &device);
+        let colors_rgb_2d_target =
+            Tensor::<NdArray, 3>::from_floats([[[0.0, 0.0, 0.5]]], &device);
+
+        let preview =
+            super::compose_preview(colors_rgb_2d.to_owned(), colors_rgb_2d_target)
+                .unwrap();
+        assert_eq!(preview.dimensions(), (3, 1));
+
+        let target = vec![255, 0, 128, 0, 0, 128, 85, 85, 85];
+        let output = preview.into_raw();
+        assert_eq!(output, target);
+
+        let colors_rgb_2d_target = Tensor::<NdArray, 3>::zeros([1, 2, 3], &device);
+        super::compose_preview(colors_rgb_2d, colors_rgb_2d_target).unwrap_err();
+    }
+}
@@ -0,0 +1,193 @@
+//! 3DGS trainer random number generation.
+
+pub use super::*;
+pub use rand::{seq::SliceRandom, Error as RandError, RngCore, SeedableRng};
+
+/// Random number generator of the trainer.
+///
+/// It is SplitMix64, whose whole state is a single integer,
+/// so the state can be stored in [`Gaussian3dTrainerRecord`]
+/// and a resumed run continues the same random stream.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TrainerRng {
+    /// State.
+    pub state: u64,
+}
+
+impl RngCore for TrainerRng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(
+        &mut self,
+        dest: &mut [u8],
+    ) {
+        dest.chunks_mut(8).for_each(|chunk| {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        });
+    }
+
+    #[inline]
+    fn try_fill_bytes(
+        &mut self,
+        dest: &mut [u8],
+    ) -> Result<(), RandError> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for TrainerRng {
+    type Seed = [u8; 8];
+
+    #[inline]
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::seed_from_u64(u64::from_le_bytes(seed))
+    }
+
+    #[inline]
+    fn seed_from_u64(state: u64) -> Self {
+        Self { state }
+    }
+}
+
+impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
+    /// Reseed the backend from the random number generator of the trainer.
+    ///
+    /// It should be called before sampling any random tensor,
+    /// so that the samples only depend on the recorded state.
+    #[inline]
+    pub fn reseed(&mut self) -> &mut Self {
+        AB::seed(self.rng.next_u64());
+        self
+    }
+
+    /// Pop the next batch of at most `count` camera IDs
+    /// from the queue of the current epoch.
+    ///
+    /// The queue is refilled with the shuffled camera IDs at each epoch.
+    /// The batch does not span two epochs, so it has no duplicate camera ID.
+    /// The queue is stored in [`Gaussian3dTrainerRecord`],
+    /// so a resumed run continues the same epoch.
+    ///
+    /// ## Returns
+    ///
+    /// The camera IDs, which are empty if there is no camera.
+    pub fn next_camera_ids(
+        &mut self,
+        cameras: &sparse_view::Cameras,
+        count: usize,
+    ) -> Vec<u32> {
+        if self.camera_ids_queue.is_empty() {
+            self.camera_ids_queue = self.shuffle_camera_ids(cameras);
+        }
+        let len = self.camera_ids_queue.len();
+        self.camera_ids_queue
+            .split_off(len.saturating_sub(count))
+            .into_iter()
+            .rev()
+            .collect()
+    }
+
+    /// Shuffle the camera IDs with the random number generator of the trainer.
+    pub fn shuffle_camera_ids(
+        &mut self,
+        cameras: &sparse_view::Cameras,
+    ) -> Vec<u32> {
+        let mut camera_ids = cameras.keys().copied().collect::<Vec<_>>();
+        camera_ids.shuffle(&mut self.rng);
+        camera_ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn resume() {
+        use super::*;
+
+        let mut rng = TrainerRng::seed_from_u64(SEED);
+        (0..5).for_each(|_| {
+            rng.next_u64();
+        });
+
+        let mut rng_resumed = TrainerRng::seed_from_u64(rng.state);
+        let target = (0..5).map(|_| rng.next_u64()).collect::<Vec<_>>();
+        let output = (0..5).map(|_| rng_resumed.next_u64()).collect::<Vec<_>>();
+        assert_eq!(output, target);
+
+        let mut rng_other = TrainerRng::seed_from_u64(SEED + 1);
+        let output = (0..5).map(|_| rng_other.next_u64()).collect::<Vec<_>>();
+        assert_ne!(output, target);
+    }
+
+    #[test]
+    fn next_camera_ids() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        let cameras = (0..5)
+            .map(|camera_id| {
+                let camera = sparse_view::Camera {
+                    camera_id,
+                    ..Default::default()
+                };
+                (camera_id, camera)
+            })
+            .collect::<sparse_view::Cameras>();
+
+        let mut trainer = Gaussian3dTrainer::<Autodiff<NdArray>>::default();
+        let target = 3;
+        let output = trainer.next_camera_ids(&cameras, 3).len();
+        assert_eq!(output, target);
+        assert_eq!(trainer.camera_ids_queue.len(), 2);
+
+        // The resumed run continues the same epoch
+        let mut trainer_resumed = Gaussian3dTrainer::<Autodiff<NdArray>>::default();
+        trainer_resumed.load_record(trainer.to_record());
+        let target = (0..3)
+            .map(|_| trainer.next_camera_ids(&cameras, 4))
+            .collect::<Vec<_>>();
+        let output = (0..3)
+            .map(|_| trainer_resumed.next_camera_ids(&cameras, 4))
+            .collect::<Vec<_>>();
+        assert_eq!(output, target);
+
+        // The batches are capped at the end of each epoch
+        let output = target.iter().map(Vec::len).collect::<Vec<_>>();
+        assert_eq!(output, vec![2, 4, 1]);
+
+        // The next epoch covers all the cameras
+        let mut output = target[1..].concat();
+        output.sort();
+        let target = vec![0, 1, 2, 3, 4];
+        assert_eq!(output, target);
+
+        // There is no camera after the epochs
+        assert!(trainer.camera_ids_queue.is_empty());
+        let output = trainer.next_camera_ids(&sparse_view::Cameras::default(), 4);
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn fill_bytes() {
+        use super::*;
+
+        let mut rng = TrainerRng::seed_from_u64(0);
+        let target = rng.to_owned().next_u64().to_le_bytes()[..5].to_vec();
+        let output = &mut [0; 5];
+        rng.fill_bytes(output);
+        assert_eq!(output.as_slice(), target);
+    }
+}
@@ -4,6 +4,8 @@ pub use super::*;
 pub use crate::range::RangeOptions;
 pub use burn::tensor::{Distribution, Int};
 
+use burn::tensor::Bool;
+
 use gausplat_renderer::scene::gaussian_3d::SH_DEGREE_MAX;
 use std::ops::Add;
 
@@ -29,6 +31,8 @@ pub struct RefinerConfig {
     #[config(default = "5e-3")]
     pub threshold_opacity: f64,
     /// Threshold for the 2D position gradient norm.
+    ///
+    /// It is compared at full resolution.
     #[config(default = "3e-4")]
     pub threshold_position_2d_grad_norm: f64,
     /// Threshold for scaling.
@@ -92,6 +96,44 @@ impl<B: Backend> Refiner<B> {
     }
 }
 
+impl<B: Backend> RefinerState<B> {
+    /// Initialize the state for the points.
+    #[inline]
+    pub fn new(
+        point_count: usize,
+        device: &B::Device,
+    ) -> Self {
+        Self {
+            positions_2d_grad_norm_sum: Tensor::zeros([point_count], device),
+            time: Tensor::ones([point_count], device),
+        }
+    }
+
+    /// Accumulate the 2D position gradient norms of the visible points.
+    ///
+    /// The norms are multiplied by `factor` before the accumulation.
+    pub fn accumulate(
+        &mut self,
+        positions_2d_grad_norm: Tensor<B, 1>,
+        is_visible: Tensor<B, 1, Bool>,
+        factor: f64,
+    ) -> &mut Self {
+        self.positions_2d_grad_norm_sum =
+            self.positions_2d_grad_norm_sum.to_owned().mask_where(
+                is_visible.to_owned(),
+                self.positions_2d_grad_norm_sum
+                    .to_owned()
+                    .add(positions_2d_grad_norm.mul_scalar(factor)),
+            );
+        self.time = self
+            .time
+            .to_owned()
+            .mask_where(is_visible, self.time.to_owned().add_scalar(1.0));
+
+        self
+    }
+}
+
 impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
     /// Refine the 3DGS scene.
     ///
@@ -103,22 +145,65 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
     /// - Split the large points.
     /// - Retain the visible points.
     /// 3. Update the optimizer records.
+    #[inline]
     pub fn refine(
         &mut self,
         scene: &mut Gaussian3dScene<AB>,
         grads: &mut AB::Gradients,
         output: Gaussian3dRenderOutputAutodiff<AB>,
+    ) -> &mut Self {
+        self.refine_batch(scene, grads, [output])
+    }
+
+    /// Refine the 3DGS scene with the render outputs of a batch.
+    ///
+    /// The statistics of the refiner are accumulated over the outputs.
+    /// The gradients are from the loss averaged over the outputs,
+    /// so they are multiplied by the output count to match single-view iterations.
+    /// See [`Gaussian3dTrainer::refine`] for the details.
+    pub fn refine_batch<I: IntoIterator<Item = Gaussian3dRenderOutputAutodiff<AB>>>(
+        &mut self,
+        scene: &mut Gaussian3dScene<AB>,
+        grads: &mut AB::Gradients,
+        outputs: I,
     ) -> &mut Self {
         // NOTE: The following factors are difficult to tune.
         const FACTOR_DEVIATION: f64 = 1.0;
         const FACTOR_SCALING_HUGE: f64 = 10.0;
         const FACTOR_SPLITTING: f64 = 0.65;
 
+        // Updating the record
+
+        // NOTE: The 2D positions are measured in pixels of the downsampled image.
+        let factor_resolution = self.schedule_resolution.factor(self.iteration) as f64;
+        let outputs = outputs.into_iter().collect::<Vec<_>>();
+        let factor = outputs.len() as f64 / factor_resolution;
+        let mut is_updated = false;
+
+        for output in outputs {
+            let Some(positions_2d_grad_norm) =
+                output.positions_2d_grad_norm_ref.grad_remove(grads)
+            else {
+                continue;
+            };
+
+            let device = &output.radii.device();
+            let point_count = output.radii.dims()[0];
+            self.refiner
+                .record
+                .get_or_insert_with(|| RefinerState::new(point_count, device))
+                .accumulate(
+                    positions_2d_grad_norm,
+                    output.radii.not_equal_elem(0),
+                    factor,
+                );
+
+            is_updated = true;
+        }
+
         // Specifying the parameters
 
-        let Some(positions_2d_grad_norm) =
-            output.positions_2d_grad_norm_ref.grad_remove(grads)
-        else {
+        let (true, Some(record)) = (is_updated, &mut self.refiner.record) else {
             return self;
         };
 
@@ -126,29 +211,9 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
         log::debug!(target: "gausplat::trainer::gaussian_3d::refine", "start");
 
         let config = &self.refiner.config;
-        let device = &output.radii.device();
-        let point_count = output.radii.dims()[0];
-        let record = self.refiner.record.get_or_insert_with(|| RefinerState {
-            positions_2d_grad_norm_sum: Tensor::zeros([point_count], device),
-            time: Tensor::ones([point_count], device),
-        });
-
-        // Updating the record
-
-        let is_visible = output.radii.not_equal_elem(0);
-
-        record.positions_2d_grad_norm_sum =
-            record.positions_2d_grad_norm_sum.to_owned().mask_where(
-                is_visible.to_owned(),
-                record
-                    .positions_2d_grad_norm_sum
-                    .to_owned()
-                    .add(positions_2d_grad_norm.to_owned()),
-            );
-        record.time = record
-            .time
-            .to_owned()
-            .mask_where(is_visible, record.time.to_owned().add_scalar(1.0));
+        let device = &record.time.device();
+        #[cfg(all(debug_assertions, not(test)))]
+        let point_count = record.time.dims()[0];
 
         // Densification
 
@@ -228,6 +293,9 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
 
             // Densifying by cloning small points
 
+            // Reseeding the backend for reproducible sampling
+            AB::seed(self.rng.next_u64());
+
             let mut points_cloned = points
                 .to_owned()
                 .map(|p| p.select(0, args_to_clone.to_owned()));
@@ -289,12 +357,21 @@ impl<AB: AutodiffBackend> Gaussian3dTrainer<AB> {
                 .set_inner_rotations(make_points(3))
                 .set_inner_scalings(make_points(4));
 
+            // Remapping the moving average of the parameters
+            self.ema.retain(args_to_retain.to_owned(), scene);
+
             let point_count_retained = points_retained[0].dims()[0];
             let point_count_cloned = points_cloned[0].dims()[0];
             let point_count_splitted = points_splitted[0].dims()[0];
             let point_count_selected = point_count_cloned + point_count_splitted;
             let point_count_new = point_count_retained + point_count_selected;
 
+            self.statistics.densification = Some(DensificationStatistics {
+                point_count_cloned,
+                point_count_retained,
+                point_count_splitted,
+            });
+
             #[cfg(all(debug_assertions, not(test)))]
             log::debug!(
                 target: "gausplat::trainer::gaussian_3d::refine",
@@ -379,3 +456,65 @@ impl Default for RefinerConfig {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn accumulate_batch() {
+        use super::*;
+        use burn::backend::NdArray;
+
+        type AB = Autodiff<NdArray>;
+
+        let device = Default::default();
+        let grads_target = [[1.0, 2.0, 0.0], [3.0, 0.0, 5.0]];
+        let is_visible = [[true, true, false], [true, false, true]]
+            .map(|v| Tensor::<NdArray, 1, Bool>::from_bool(v.into(), &device));
+        // The references of 2D position gradient norms
+        let references =
+            || grads_target.map(|_| Tensor::<AB, 1>::zeros([3], &device).require_grad());
+        let get_loss = |reference: Tensor<AB, 1>, grad: [f64; 3]| {
+            reference
+                .mul(Tensor::from_floats(grad.map(|v| v as f32), &device))
+                .sum()
+        };
+
+        // Two single-view iterations
+        let mut target = RefinerState::<NdArray>::new(3, &device);
+        for (index, reference) in references().into_iter().enumerate() {
+            let grads = get_loss(reference.to_owned(), grads_target[index]).backward();
+            let grad = reference.grad(&grads).unwrap();
+            target.accumulate(grad, is_visible[index].to_owned(), 1.0);
+        }
+
+        // A batch of two views with the averaged loss
+        let mut output = RefinerState::<NdArray>::new(3, &device);
+        let references = references();
+        let losses = references
+            .iter()
+            .zip(grads_target)
+            .map(|(reference, grad)| get_loss(reference.to_owned(), grad))
+            .collect();
+        let grads = Tensor::cat(losses, 0).mean().backward();
+        for (index, reference) in references.into_iter().enumerate() {
+            let grad = reference.grad(&grads).unwrap();
+            output.accumulate(grad, is_visible[index].to_owned(), 2.0);
+        }
+
+        let target_sum = Tensor::<NdArray, 1>::from_floats([4.0, 2.0, 5.0], &device);
+        target
+            .positions_2d_grad_norm_sum
+            .to_owned()
+            .into_data()
+            .assert_approx_eq(&target_sum.into_data(), 6);
+
+        output
+            .positions_2d_grad_norm_sum
+            .into_data()
+            .assert_approx_eq(&target.positions_2d_grad_norm_sum.into_data(), 6);
+        output
+            .time
+            .into_data()
+            .assert_approx_eq(&target.time.into_data(), 6);
+    }
+}
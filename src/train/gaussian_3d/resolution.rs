@@ -0,0 +1,196 @@
+//! 3DGS resolution schedule implementation.
+
+pub use super::*;
+
+use std::{collections::BTreeMap, fmt};
+
+/// Resolution schedule for coarse-to-fine training.
+///
+/// The images are downsampled by `factor_start` at the beginning,
+/// and the factor is halved every `step` iterations until full resolution.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub struct ResolutionSchedule {
+    /// The downsampling factor at the beginning.
+    ///
+    /// `1` means full resolution all the time.
+    #[config(default = "1")]
+    pub factor_start: u32,
+    /// The iteration count to halve the downsampling factor.
+    ///
+    /// `0` means full resolution all the time.
+    #[config(default = "250")]
+    pub step: u64,
+}
+
+/// Cache of the downsampled cameras for the resolution schedule.
+///
+/// Each camera is downsampled once per factor,
+/// and the cache is cleared when the factor changes.
+#[derive(Clone, Default)]
+pub struct ResolutionCache {
+    /// Downsampled cameras by the camera ID.
+    pub cameras: BTreeMap<u32, sparse_view::Camera>,
+    /// Downsampling factor of the cameras.
+    pub factor: u32,
+}
+
+impl ResolutionSchedule {
+    /// Return the downsampling factor at the iteration.
+    pub fn factor(
+        &self,
+        iteration: u64,
+    ) -> u32 {
+        let halving_count = iteration.checked_div(self.step).unwrap_or(u64::MAX);
+
+        self.factor_start
+            .checked_shr(halving_count.min(u32::MAX as u64) as u32)
+            .unwrap_or_default()
+            .max(1)
+    }
+
+    /// Downsample the camera for the iteration.
+    ///
+    /// ## Returns
+    ///
+    /// `None` if the camera is at full resolution.
+    pub fn downsample(
+        &self,
+        iteration: u64,
+        camera: &sparse_view::Camera,
+    ) -> Result<Option<sparse_view::Camera>, Error> {
+        let factor = self.factor(iteration);
+        if factor == 1 {
+            return Ok(None);
+        }
+
+        let mut camera = camera.to_owned();
+        camera.resize_max(camera.size_max().div_ceil(factor))?;
+
+        Ok(Some(camera))
+    }
+}
+
+impl ResolutionCache {
+    /// Downsample the cameras for the iteration if they are not cached.
+    ///
+    /// See [`ResolutionSchedule::downsample`] for the details.
+    pub fn update(
+        &mut self,
+        schedule: &ResolutionSchedule,
+        iteration: u64,
+        cameras: &[&sparse_view::Camera],
+    ) -> Result<&mut Self, Error> {
+        let factor = schedule.factor(iteration);
+        if factor != self.factor {
+            self.cameras.clear();
+            self.factor = factor;
+        }
+
+        for camera in cameras {
+            if self.cameras.contains_key(&camera.camera_id) {
+                continue;
+            }
+            if let Some(camera) = schedule.downsample(iteration, camera)? {
+                self.cameras.insert(camera.camera_id, camera);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Return the downsampled camera if cached, otherwise the camera itself.
+    #[inline]
+    pub fn get<'a>(
+        &'a self,
+        camera: &'a sparse_view::Camera,
+    ) -> &'a sparse_view::Camera {
+        self.cameras.get(&camera.camera_id).unwrap_or(camera)
+    }
+}
+
+impl fmt::Debug for ResolutionCache {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.debug_struct("ResolutionCache")
+            .field("cameras.len()", &self.cameras.len())
+            .field("factor", &self.factor)
+            .finish()
+    }
+}
+
+impl Default for ResolutionSchedule {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn factor() {
+        use super::*;
+
+        let schedule = ResolutionSchedule::new()
+            .with_factor_start(8)
+            .with_step(100);
+
+        let target = [8, 8, 4, 2, 1, 1, 1];
+        let output = [0, 99, 100, 250, 300, 1000, u64::MAX].map(|i| schedule.factor(i));
+        assert_eq!(output, target);
+
+        let target = 1;
+        let output = ResolutionSchedule::default().factor(0);
+        assert_eq!(output, target);
+
+        let target = 1;
+        let output = schedule.with_step(0).factor(0);
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn update_cache() {
+        use super::*;
+
+        let mut image_encoded = vec![];
+        image::RgbImage::new(8, 6)
+            .write_to(
+                &mut std::io::Cursor::new(&mut image_encoded),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        let camera = sparse_view::Camera {
+            camera_id: 3,
+            image: sparse_view::Image {
+                image_encoded,
+                ..Default::default()
+            },
+            view: sparse_view::View {
+                image_height: 6,
+                image_width: 8,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let schedule = ResolutionSchedule::new()
+            .with_factor_start(2)
+            .with_step(100);
+        let mut cache = ResolutionCache::default();
+
+        // The camera is not downsampled again
+        cache.update(&schedule, 0, &[&camera]).unwrap();
+        assert_eq!(cache.factor, 2);
+        assert_eq!(cache.cameras.len(), 1);
+        cache.cameras.get_mut(&3).unwrap().sensor_id = 1;
+        cache.update(&schedule, 1, &[&camera]).unwrap();
+        assert_eq!(cache.get(&camera).sensor_id, 1);
+
+        // The cache is cleared at full resolution
+        cache.update(&schedule, 100, &[&camera]).unwrap();
+        assert_eq!(cache.factor, 1);
+        assert!(cache.cameras.is_empty());
+        assert_eq!(cache.get(&camera), &camera);
+    }
+}
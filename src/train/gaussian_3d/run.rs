@@ -0,0 +1,510 @@
+//! 3DGS training runs.
+
+pub use super::*;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Token to cancel a training run cooperatively.
+///
+/// The clones share the cancellation,
+/// so the run can be cancelled from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    is_cancelled: Arc<AtomicBool>,
+}
+
+/// Configuration for a training run.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub struct Gaussian3dRunConfig {
+    /// Max count of cameras in a batch.
+    ///
+    /// The last batch of an epoch has the remaining cameras only.
+    #[config(default = "1")]
+    pub batch_size: usize,
+    /// Max wall-clock duration of the run.
+    ///
+    /// `None` means no time budget.
+    #[config(default = "None")]
+    pub duration_max: Option<Duration>,
+    /// Early stopping on the validation plateau.
+    ///
+    /// `None` disables the early stopping.
+    #[config(default = "None")]
+    pub early_stopping: Option<EarlyStoppingConfig>,
+    /// Whether to shorten the range for densification
+    /// if `iteration_max` is not estimated to be reached within `duration_max`.
+    #[config(default = "true")]
+    pub is_densification_shortened: bool,
+    /// Iteration to stop at.
+    #[config(default = "30000")]
+    pub iteration_max: u64,
+}
+
+/// Early stopping on the validation plateau.
+///
+/// It is a state machine over the validation PSNRs, see [`EarlyStopping::update`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EarlyStopping {
+    /// Configuration.
+    pub config: EarlyStoppingConfig,
+    /// Count of evaluations since the last improvement by `threshold_psnr`.
+    pub evaluation_count_stale: usize,
+    /// Best validation PSNR.
+    pub psnr_best: Option<f64>,
+}
+
+/// Configuration for early stopping on the validation plateau.
+///
+/// The run stops if the validation PSNR is not improved
+/// by `threshold_psnr` for `patience` evaluations.
+#[derive(Config, Copy, Debug, PartialEq)]
+pub struct EarlyStoppingConfig {
+    /// Count of evaluations without improvement to stop after.
+    #[config(default = "5")]
+    pub patience: usize,
+    /// Range for evaluating the validation cameras.
+    #[config(default = "RangeOptions::new(1000, u64::MAX, 1000)")]
+    pub range_evaluation: RangeOptions,
+    /// Min increase of the validation PSNR in dB to be an improvement.
+    #[config(default = "0.05")]
+    pub threshold_psnr: f64,
+}
+
+/// Status of a stopped training run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Gaussian3dRunStatus {
+    /// The run was cancelled by the token.
+    Cancelled,
+    /// The run reached the max iteration.
+    Completed,
+    /// The validation PSNR reached a plateau.
+    EarlyStopped,
+    /// The run reached the max duration.
+    TimedOut,
+}
+
+/// Summary of a stopped training run.
+#[derive(Clone, Debug)]
+pub struct Gaussian3dRunSummary<B: Backend> {
+    /// Final checkpoint of the trainer.
+    pub checkpoint: Gaussian3dTrainerRecord<B>,
+    /// Wall-clock duration of the run.
+    pub duration: Duration,
+    /// Early stopping criterion of the run.
+    pub early_stopping: Option<EarlyStoppingConfig>,
+    /// Best evaluation on the validation cameras.
+    pub evaluation_best: Option<Gaussian3dEvaluation>,
+    /// Iteration of the trainer when the run stopped.
+    pub iteration: u64,
+    /// Count of iterations trained in the run.
+    pub iteration_count: u64,
+    /// Scene of the best evaluation on the validation cameras.
+    pub scene_best: Option<Gaussian3dScene<Autodiff<B>>>,
+    /// Status.
+    pub status: Gaussian3dRunStatus,
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the run.
+    #[inline]
+    pub fn cancel(&self) {
+        self.is_cancelled.store(true, Ordering::Release);
+    }
+
+    /// Return `true` if the run is cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled.load(Ordering::Acquire)
+    }
+}
+
+impl<B: Backend> Gaussian3dTrainer<Autodiff<B>>
+where
+    Gaussian3dScene<Autodiff<B>>: Gaussian3dRenderer<B>,
+{
+    /// Train the 3DGS scene on the cameras until the run stops.
+    ///
+    /// The cameras are shuffled at each epoch,
+    /// see [`Gaussian3dTrainer::next_camera_ids`].
+    /// The run stops before the next iteration if one of the following holds:
+    ///
+    /// 1. The token is cancelled.
+    /// 2. The iteration reaches `iteration_max`.
+    /// 3. The elapsed time reaches `duration_max`.
+    ///
+    /// A final checkpoint is taken when the run stops,
+    /// see [`Gaussian3dTrainer::checkpoint`].
+    #[inline]
+    pub fn fit(
+        &mut self,
+        scene: &mut Gaussian3dScene<Autodiff<B>>,
+        cameras: &sparse_view::Cameras,
+        config: &Gaussian3dRunConfig,
+        token: &CancellationToken,
+    ) -> Result<Gaussian3dRunSummary<B>, Error> {
+        self.fit_with_validation(scene, cameras, &Default::default(), config, token)
+    }
+
+    /// Train the 3DGS scene on the cameras until the run stops,
+    /// evaluating the validation cameras for early stopping.
+    ///
+    /// See [`Gaussian3dTrainer::fit`] for the other stopping conditions.
+    /// The early stopping is disabled if there is no validation camera.
+    ///
+    /// The scene is left as of the last iteration, and
+    /// the best scene is kept in [`Gaussian3dRunSummary::scene_best`].
+    pub fn fit_with_validation(
+        &mut self,
+        scene: &mut Gaussian3dScene<Autodiff<B>>,
+        cameras: &sparse_view::Cameras,
+        cameras_validation: &sparse_view::Cameras,
+        config: &Gaussian3dRunConfig,
+        token: &CancellationToken,
+    ) -> Result<Gaussian3dRunSummary<B>, Error> {
+        let time_start = Instant::now();
+        let iteration_start = self.iteration;
+        let range_densification = self.refiner.config.range_densification;
+        let cameras_validation = cameras_validation.values().collect::<Vec<_>>();
+        let mut early_stopping = config
+            .early_stopping
+            .filter(|_| !cameras_validation.is_empty())
+            .map(EarlyStoppingConfig::init);
+        let mut evaluation_best = None::<Gaussian3dEvaluation>;
+        let mut scene_best = None;
+
+        let status = loop {
+            let duration = time_start.elapsed();
+            let status =
+                config.get_status(self.iteration, duration, token.is_cancelled());
+            if let Some(status) = status {
+                break status;
+            }
+            if cameras.is_empty() {
+                break Gaussian3dRunStatus::Completed;
+            }
+
+            // Shortening the range for densification
+            let ratio =
+                config.get_densification_ratio(iteration_start, self.iteration, duration);
+            if let Some(ratio) = ratio {
+                self.refiner.config.range_densification =
+                    range_densification.scale_end(ratio);
+            }
+
+            let batch = self
+                .next_camera_ids(cameras, config.batch_size.max(1))
+                .into_iter()
+                .map(|camera_id| {
+                    cameras
+                        .get(&camera_id)
+                        .ok_or(Error::UnknownCameraId(camera_id))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            self.train_batch(scene, &batch)?;
+
+            // Stopping early on the validation plateau
+
+            let Some(early_stopping) = &mut early_stopping else {
+                continue;
+            };
+            if !early_stopping.config.range_evaluation.has(self.iteration) {
+                continue;
+            }
+
+            let evaluation = self.evaluate(scene, &cameras_validation)?;
+            if early_stopping.update(evaluation.psnr) {
+                evaluation_best = Some(evaluation);
+                scene_best = Some(scene.to_owned());
+            }
+            if early_stopping.is_stopped() {
+                break Gaussian3dRunStatus::EarlyStopped;
+            }
+        };
+
+        self.refiner.config.range_densification = range_densification;
+
+        #[cfg(all(debug_assertions, not(test)))]
+        log::debug!(
+            target: "gausplat::trainer::gaussian_3d::run",
+            "fit_with_validation > {status:?} at iteration {}",
+            self.iteration,
+        );
+
+        Ok(Gaussian3dRunSummary {
+            checkpoint: self.checkpoint(scene),
+            duration: time_start.elapsed(),
+            early_stopping: early_stopping.map(|early_stopping| early_stopping.config),
+            evaluation_best,
+            iteration: self.iteration,
+            iteration_count: self.iteration - iteration_start,
+            scene_best,
+            status,
+        })
+    }
+}
+
+impl EarlyStoppingConfig {
+    /// Initialize the early stopping.
+    #[inline]
+    pub fn init(self) -> EarlyStopping {
+        EarlyStopping {
+            config: self,
+            evaluation_count_stale: 0,
+            psnr_best: None,
+        }
+    }
+}
+
+impl EarlyStopping {
+    /// Update the state with the validation PSNR.
+    ///
+    /// The best PSNR is tracked by the raw value,
+    /// while the stale count is reset only if the PSNR is better
+    /// than the best one by at least `threshold_psnr`.
+    ///
+    /// ## Returns
+    ///
+    /// `true` if the PSNR is the best so far.
+    pub fn update(
+        &mut self,
+        psnr: f64,
+    ) -> bool {
+        let (is_best, is_improved) = match self.psnr_best {
+            Some(psnr_best) => (
+                psnr > psnr_best,
+                psnr >= psnr_best + self.config.threshold_psnr,
+            ),
+            None => (true, true),
+        };
+
+        if is_improved {
+            self.evaluation_count_stale = 0;
+        } else {
+            self.evaluation_count_stale += 1;
+        }
+        if is_best {
+            self.psnr_best = Some(psnr);
+        }
+
+        is_best
+    }
+
+    /// Return `true` if the run should stop,
+    /// i.e., the stale count reaches `patience`.
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.evaluation_count_stale >= self.config.patience
+    }
+}
+
+impl Gaussian3dRunConfig {
+    /// Return the status if the run should stop before the next iteration.
+    ///
+    /// See [`Gaussian3dTrainer::fit`] for the order of the conditions.
+    pub fn get_status(
+        &self,
+        iteration: u64,
+        duration: Duration,
+        is_cancelled: bool,
+    ) -> Option<Gaussian3dRunStatus> {
+        if is_cancelled {
+            return Some(Gaussian3dRunStatus::Cancelled);
+        }
+        if iteration >= self.iteration_max {
+            return Some(Gaussian3dRunStatus::Completed);
+        }
+        if self
+            .duration_max
+            .is_some_and(|duration_max| duration >= duration_max)
+        {
+            return Some(Gaussian3dRunStatus::TimedOut);
+        }
+        None
+    }
+
+    /// Return the ratio to scale the end of the range for densification.
+    ///
+    /// It is the iteration estimated to be reached within `duration_max`
+    /// over `iteration_max`, which is at most `1`.
+    ///
+    /// ## Returns
+    ///
+    /// `None` if the range should not be shortened or
+    /// no iteration has been trained in the run.
+    pub fn get_densification_ratio(
+        &self,
+        iteration_start: u64,
+        iteration: u64,
+        duration: Duration,
+    ) -> Option<f64> {
+        let duration_max = self
+            .duration_max
+            .filter(|_| self.is_densification_shortened)?;
+        let iteration_count = iteration.saturating_sub(iteration_start);
+        if iteration_count == 0 || duration.is_zero() {
+            return None;
+        }
+
+        let iteration_estimated = iteration_start as f64
+            + iteration_count as f64 * duration_max.as_secs_f64()
+                / duration.as_secs_f64();
+        Some((iteration_estimated / self.iteration_max as f64).min(1.0))
+    }
+}
+
+impl Default for EarlyStoppingConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Gaussian3dRunConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn cancel() {
+        use super::*;
+
+        let token = CancellationToken::new();
+        assert!(!token.is_cancelled());
+
+        let token_cloned = token.to_owned();
+        std::thread::spawn(move || token_cloned.cancel())
+            .join()
+            .unwrap();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn early_stopping() {
+        use super::*;
+
+        let config = EarlyStoppingConfig::new()
+            .with_patience(2)
+            .with_threshold_psnr(0.05);
+
+        // The slight improvements are the best but do not reset the stale count
+        let mut early_stopping = config.init();
+        let target = vec![(true, 0), (true, 1), (false, 2)];
+        let output = [20.0, 20.03, 20.01]
+            .into_iter()
+            .map(|psnr| {
+                let is_best = early_stopping.update(psnr);
+                (is_best, early_stopping.evaluation_count_stale)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(output, target);
+        assert_eq!(early_stopping.psnr_best, Some(20.03));
+        assert!(early_stopping.is_stopped());
+
+        // The improvement by the threshold resets the stale count
+        let mut early_stopping = config.init();
+        let target = vec![(true, 0), (true, 1), (true, 0), (false, 1)];
+        let output = [20.0, 20.03, 20.1, 19.0]
+            .into_iter()
+            .map(|psnr| {
+                let is_best = early_stopping.update(psnr);
+                (is_best, early_stopping.evaluation_count_stale)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(output, target);
+        assert_eq!(early_stopping.psnr_best, Some(20.1));
+        assert!(!early_stopping.is_stopped());
+    }
+
+    #[test]
+    fn get_densification_ratio() {
+        use super::*;
+
+        let config = Gaussian3dRunConfig::new()
+            .with_duration_max(Some(Duration::from_secs(100)))
+            .with_iteration_max(1000);
+        let range_densification = RangeOptions::new(500, 15000, 100);
+
+        // 200 iterations in 50 seconds are estimated to reach 400 iterations
+        let target = RangeOptions::new(500, 6300, 100);
+        let output = range_densification.scale_end(
+            config
+                .get_densification_ratio(0, 200, Duration::from_secs(50))
+                .unwrap(),
+        );
+        assert_eq!(output, target);
+
+        // The resumed run is estimated from the iteration to start from
+        let target = 0.9;
+        let output = config
+            .get_densification_ratio(500, 600, Duration::from_secs(25))
+            .unwrap();
+        assert_eq!(output, target);
+
+        let target = 1.0;
+        let output = config
+            .get_densification_ratio(0, 200, Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(output, target);
+
+        let output = config.get_densification_ratio(200, 200, Duration::from_secs(10));
+        assert_eq!(output, None);
+
+        let output = config
+            .with_is_densification_shortened(false)
+            .get_densification_ratio(0, 200, Duration::from_secs(50));
+        assert_eq!(output, None);
+
+        let output = config.with_duration_max(None).get_densification_ratio(
+            0,
+            200,
+            Duration::from_secs(50),
+        );
+        assert_eq!(output, None);
+    }
+
+    #[test]
+    fn get_status() {
+        use super::*;
+
+        let config = Gaussian3dRunConfig::new()
+            .with_duration_max(Some(Duration::from_secs(100)))
+            .with_iteration_max(1000);
+
+        let output = config.get_status(999, Duration::from_secs(99), false);
+        assert_eq!(output, None);
+
+        let target = Some(Gaussian3dRunStatus::Cancelled);
+        let output = config.get_status(1000, Duration::from_secs(100), true);
+        assert_eq!(output, target);
+
+        let target = Some(Gaussian3dRunStatus::Completed);
+        let output = config.get_status(1000, Duration::from_secs(100), false);
+        assert_eq!(output, target);
+
+        let target = Some(Gaussian3dRunStatus::TimedOut);
+        let output = config.get_status(999, Duration::from_secs(100), false);
+        assert_eq!(output, target);
+
+        let output = config
+            .with_duration_max(None)
+            .get_status(999, Duration::MAX, false);
+        assert_eq!(output, None);
+    }
+}
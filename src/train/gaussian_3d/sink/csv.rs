@@ -0,0 +1,117 @@
+//! CSV metrics writer.
+
+pub use super::*;
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// Writer of the scalars of a group to CSV.
+///
+/// The columns are `step` and the scalar names of the first write.
+/// The missing values are left empty,
+/// and the scalars of the other groups are ignored.
+#[derive(Clone, Debug)]
+pub struct CsvMetricsWriter<W> {
+    /// Group to write.
+    pub group: String,
+    /// Scalar names of the columns after `step`.
+    pub header: Option<Vec<String>>,
+    /// Writer.
+    pub writer: W,
+}
+
+impl CsvMetricsWriter<BufWriter<File>> {
+    /// Create the CSV file at `path` for the group.
+    pub fn create(
+        path: impl AsRef<Path>,
+        group: impl Into<String>,
+    ) -> Result<Self, Error> {
+        Ok(Self::new(BufWriter::new(File::create(path)?), group))
+    }
+}
+
+impl<W: Write> CsvMetricsWriter<W> {
+    /// Write the scalars of the group to the writer.
+    #[inline]
+    pub fn new(
+        writer: W,
+        group: impl Into<String>,
+    ) -> Self {
+        Self {
+            group: group.into(),
+            header: None,
+            writer,
+        }
+    }
+}
+
+impl<W: Send + Write> MetricsWriter for CsvMetricsWriter<W> {
+    fn write_scalars(
+        &mut self,
+        group: &str,
+        step: u64,
+        scalars: &[Scalar],
+    ) -> Result<(), Error> {
+        if group != self.group {
+            return Ok(());
+        }
+
+        let header = match &self.header {
+            Some(header) => header,
+            None => {
+                let header = scalars
+                    .iter()
+                    .map(|(name, _)| name.to_string())
+                    .collect::<Vec<_>>();
+                write!(self.writer, "step")?;
+                header
+                    .iter()
+                    .try_for_each(|name| write!(self.writer, ",{name}"))?;
+                writeln!(self.writer)?;
+                self.header.insert(header)
+            },
+        };
+
+        write!(self.writer, "{step}")?;
+        for name in header {
+            let value = scalars
+                .iter()
+                .find(|(name_scalar, _)| name_scalar == name)
+                .and_then(|(_, value)| *value);
+            match value {
+                Some(value) => write!(self.writer, ",{value}")?,
+                None => write!(self.writer, ",")?,
+            }
+        }
+        writeln!(self.writer)?;
+
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn write_scalars() {
+        use super::*;
+
+        let mut writer = CsvMetricsWriter::new(vec![], GROUP_EVAL);
+        writer
+            .write_scalars(GROUP_EVAL, 7, &[("mae", Some(0.5)), ("psnr", None)])
+            .unwrap();
+        writer
+            .write_scalars(GROUP_TRAIN, 8, &[("loss", Some(1.0))])
+            .unwrap();
+        writer
+            .write_scalars(GROUP_EVAL, 9, &[("psnr", Some(30.0)), ("mae", Some(0.25))])
+            .unwrap();
+
+        let target = "step,mae,psnr\n7,0.5,\n9,0.25,30\n";
+        let output = String::from_utf8(writer.writer).unwrap();
+        assert_eq!(output, target);
+    }
+}
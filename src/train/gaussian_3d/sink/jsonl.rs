@@ -0,0 +1,117 @@
+//! JSON Lines metrics writer.
+
+pub use super::*;
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// Writer of the scalars of all the groups to JSON Lines.
+///
+/// Each line is an object with the keys `group`, `step` and the scalar names.
+/// The missing and non-finite values are `null`.
+#[derive(Clone, Debug)]
+pub struct JsonLinesMetricsWriter<W> {
+    /// Writer.
+    pub writer: W,
+}
+
+impl JsonLinesMetricsWriter<BufWriter<File>> {
+    /// Create the JSON Lines file at `path`.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> JsonLinesMetricsWriter<W> {
+    /// Write the scalars to the writer.
+    #[inline]
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Send + Write> MetricsWriter for JsonLinesMetricsWriter<W> {
+    fn write_scalars(
+        &mut self,
+        group: &str,
+        step: u64,
+        scalars: &[Scalar],
+    ) -> Result<(), Error> {
+        write!(self.writer, "{{\"group\":")?;
+        encode_json_string(&mut self.writer, group)?;
+        write!(self.writer, ",\"step\":{step}")?;
+        for (name, value) in scalars {
+            write!(self.writer, ",")?;
+            encode_json_string(&mut self.writer, name)?;
+            match value {
+                Some(value) if value.is_finite() => write!(self.writer, ":{value}")?,
+                _ => write!(self.writer, ":null")?,
+            }
+        }
+        writeln!(self.writer, "}}")?;
+
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Encode the string as a JSON string.
+fn encode_json_string<W: Write>(
+    writer: &mut W,
+    value: &str,
+) -> Result<(), Error> {
+    write!(writer, "\"")?;
+    for char in value.chars() {
+        match char {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            '\n' => write!(writer, "\\n")?,
+            '\r' => write!(writer, "\\r")?,
+            '\t' => write!(writer, "\\t")?,
+            char if char.is_control() => write!(writer, "\\u{:04x}", char as u32)?,
+            char => write!(writer, "{char}")?,
+        }
+    }
+    write!(writer, "\"")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn write_scalars() {
+        use super::*;
+
+        let mut writer = JsonLinesMetricsWriter::new(vec![]);
+        writer
+            .write_scalars(
+                GROUP_TRAIN,
+                1,
+                &[("loss", Some(0.5)), ("point_count_cloned", None)],
+            )
+            .unwrap();
+        writer
+            .write_scalars(GROUP_EVAL, 2, &[("psnr", Some(f64::INFINITY))])
+            .unwrap();
+
+        let target = concat!(
+            "{\"group\":\"train\",\"step\":1,\"loss\":0.5,\"point_count_cloned\":null}\n",
+            "{\"group\":\"eval\",\"step\":2,\"psnr\":null}\n",
+        );
+        let output = String::from_utf8(writer.writer).unwrap();
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn encode_json_string() {
+        let mut output = vec![];
+        super::encode_json_string(&mut output, "a\"b\\c\n\u{1}").unwrap();
+
+        let target = "\"a\\\"b\\\\c\\n\\u0001\"";
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, target);
+    }
+}
@@ -0,0 +1,225 @@
+//! 3DGS training metrics sinks.
+//!
+//! The writers record the scalars of each group locally:
+//! - [`CsvMetricsWriter`] writes a CSV file for a group.
+//! - [`JsonLinesMetricsWriter`] writes a JSON object per line.
+//! - [`TensorBoardMetricsWriter`] writes a TensorBoard event file (`tfevents`).
+//!
+//! Only the TensorBoard writer records the preview images.
+//!
+//! All the writers flush after each write, so the files of a partial run
+//! remain readable.
+
+pub mod csv;
+pub mod jsonl;
+pub mod tensorboard;
+
+pub use super::*;
+pub use csv::*;
+pub use jsonl::*;
+pub use tensorboard::*;
+
+use image::RgbImage;
+
+/// Group of the training statistics.
+pub const GROUP_TRAIN: &str = "train";
+
+/// Group of the evaluation results.
+pub const GROUP_EVAL: &str = "eval";
+
+/// Group of the preview images.
+pub const GROUP_PREVIEW: &str = "preview";
+
+/// Named scalar, whose value is `None` if it is missing.
+pub type Scalar<'a> = (&'a str, Option<f64>);
+
+/// Writer of training metrics.
+pub trait MetricsWriter: Send {
+    /// Write the scalars of the group at the step.
+    fn write_scalars(
+        &mut self,
+        group: &str,
+        step: u64,
+        scalars: &[Scalar],
+    ) -> Result<(), Error>;
+
+    /// Write the image of the group at the step.
+    ///
+    /// It does nothing by default.
+    fn write_image(
+        &mut self,
+        _group: &str,
+        _tag: &str,
+        _step: u64,
+        _image: &RgbImage,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Hook writing the training statistics and the evaluation results.
+#[derive(Clone, Debug)]
+pub struct MetricsHook<W> {
+    /// Range of iterations to write the training statistics.
+    pub range_statistics: RangeOptions,
+    /// Writer.
+    pub writer: W,
+}
+
+impl<W: MetricsWriter> MetricsHook<W> {
+    /// Write the training statistics at every iteration.
+    #[inline]
+    pub fn new(writer: W) -> Self {
+        Self {
+            range_statistics: Default::default(),
+            writer,
+        }
+    }
+
+    /// Set the range of iterations to write the training statistics.
+    #[inline]
+    pub fn with_range_statistics(
+        mut self,
+        range_statistics: RangeOptions,
+    ) -> Self {
+        self.range_statistics = range_statistics;
+        self
+    }
+}
+
+impl<AB: AutodiffBackend, W: MetricsWriter> Gaussian3dTrainerHook<AB> for MetricsHook<W> {
+    fn on_iteration_end(
+        &mut self,
+        trainer: &Gaussian3dTrainer<AB>,
+        _scene: &Gaussian3dScene<AB>,
+    ) {
+        if !self.range_statistics.has(trainer.iteration) {
+            return;
+        }
+
+        let statistics = &trainer.statistics;
+        let result = self.writer.write_scalars(
+            GROUP_TRAIN,
+            statistics.iteration,
+            &statistics.to_scalars(),
+        );
+        if let Err(error) = result {
+            log::warn!(
+                target: "gausplat::trainer::gaussian_3d::sink",
+                "MetricsHook::on_iteration_end > {error}",
+            );
+        }
+    }
+
+    fn on_evaluation(
+        &mut self,
+        _trainer: &Gaussian3dTrainer<AB>,
+        _scene: &Gaussian3dScene<AB>,
+        evaluation: &Gaussian3dEvaluation,
+    ) {
+        let result = self.writer.write_scalars(
+            GROUP_EVAL,
+            evaluation.iteration,
+            &evaluation.to_scalars(),
+        );
+        if let Err(error) = result {
+            log::warn!(
+                target: "gausplat::trainer::gaussian_3d::sink",
+                "MetricsHook::on_evaluation > {error}",
+            );
+        }
+    }
+}
+
+impl Gaussian3dTrainerStatistics {
+    /// Return the scalars in a fixed order.
+    ///
+    /// The scalars of densification are missing in the other iterations,
+    /// and so are the losses out of the range for loss statistics.
+    pub fn to_scalars(&self) -> Vec<Scalar<'static>> {
+        let densification = self.densification.as_ref();
+        vec![
+            ("camera_count", Some(self.camera_count as f64)),
+            (
+                "colors_sh_degree_max",
+                Some(self.colors_sh_degree_max as f64),
+            ),
+            ("duration", Some(self.duration.as_secs_f64())),
+            (
+                "learning_rate_colors_sh",
+                Some(self.learning_rate_colors_sh),
+            ),
+            (
+                "learning_rate_opacities",
+                Some(self.learning_rate_opacities),
+            ),
+            (
+                "learning_rate_positions",
+                Some(self.learning_rate_positions),
+            ),
+            (
+                "learning_rate_rotations",
+                Some(self.learning_rate_rotations),
+            ),
+            ("learning_rate_scalings", Some(self.learning_rate_scalings)),
+            ("loss", self.loss),
+            ("point_count", Some(self.point_count as f64)),
+            (
+                "point_count_cloned",
+                densification.map(|d| d.point_count_cloned as f64),
+            ),
+            (
+                "point_count_retained",
+                densification.map(|d| d.point_count_retained as f64),
+            ),
+            (
+                "point_count_splitted",
+                densification.map(|d| d.point_count_splitted as f64),
+            ),
+        ]
+    }
+}
+
+impl Gaussian3dEvaluation {
+    /// Return the scalars in a fixed order.
+    pub fn to_scalars(&self) -> Vec<Scalar<'static>> {
+        vec![
+            ("camera_count", Some(self.camera_count as f64)),
+            ("mae", Some(self.mae)),
+            ("psnr", Some(self.psnr)),
+            ("ssim", Some(self.ssim)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn to_scalars() {
+        use super::*;
+
+        let statistics = Gaussian3dTrainerStatistics {
+            densification: Some(DensificationStatistics {
+                point_count_cloned: 3,
+                point_count_retained: 10,
+                point_count_splitted: 4,
+            }),
+            loss: Some(0.25),
+            ..Default::default()
+        };
+
+        let output = statistics.to_scalars();
+        assert!(output.contains(&("loss", Some(0.25))));
+        assert!(output.contains(&("point_count_splitted", Some(4.0))));
+        assert!(output.contains(&("point_count_cloned", Some(3.0))));
+        assert!(output.contains(&("point_count_retained", Some(10.0))));
+
+        let target = output.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        let output = Gaussian3dTrainerStatistics::default()
+            .to_scalars()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(output, target);
+    }
+}
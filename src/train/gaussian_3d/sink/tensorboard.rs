@@ -0,0 +1,400 @@
+//! TensorBoard metrics writer.
+//!
+//! ## Format
+//!
+//! An event file is a sequence of records. Each record is:
+//!
+//! 1. Byte length of the data (`u64`, little-endian).
+//! 2. Masked CRC-32C of the length (`u32`, little-endian).
+//! 3. Data, which is an `Event` message encoded in Protocol Buffers.
+//! 4. Masked CRC-32C of the data (`u32`, little-endian).
+//!
+//! The first event holds the file version,
+//! and each later event holds a summary of scalars or an image.
+
+pub use super::*;
+
+use image::RgbImage;
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Cursor, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Version of the event file.
+pub const TENSORBOARD_FILE_VERSION: &str = "brain.Event:2";
+
+/// Writer of the scalars and images to a TensorBoard event file.
+///
+/// The tags are `{group}/{name}`. The missing values are skipped.
+#[derive(Clone, Debug)]
+pub struct TensorBoardMetricsWriter<W> {
+    /// Writer.
+    pub writer: W,
+}
+
+impl TensorBoardMetricsWriter<BufWriter<File>> {
+    /// Create an event file in the directory.
+    ///
+    /// The directory is created if it does not exist.
+    pub fn create(directory: impl AsRef<Path>) -> Result<Self, Error> {
+        let directory = directory.as_ref();
+        let time = get_wall_time();
+        let path = directory.join(format!(
+            "events.out.tfevents.{}.gausplat.{}",
+            time as u64,
+            std::process::id(),
+        ));
+
+        fs::create_dir_all(directory)?;
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> TensorBoardMetricsWriter<W> {
+    /// Write the events to the writer.
+    ///
+    /// The event of the file version is written.
+    pub fn new(writer: W) -> Result<Self, Error> {
+        let mut writer = Self { writer };
+
+        let mut event = encode_event_header(get_wall_time(), 0);
+        encode_bytes_field(&mut event, 3, TENSORBOARD_FILE_VERSION.as_bytes());
+        writer.write_event(&event)?;
+
+        Ok(writer)
+    }
+
+    /// Write the encoded event as a record, then flush the writer.
+    pub fn write_event(
+        &mut self,
+        event: &[u8],
+    ) -> Result<&mut Self, Error> {
+        let len = (event.len() as u64).to_le_bytes();
+        self.writer.write_all(&len)?;
+        self.writer
+            .write_all(&mask_crc32c(crc32c(&len)).to_le_bytes())?;
+        self.writer.write_all(event)?;
+        self.writer
+            .write_all(&mask_crc32c(crc32c(event)).to_le_bytes())?;
+
+        self.writer.flush()?;
+        Ok(self)
+    }
+}
+
+impl<W: Send + Write> MetricsWriter for TensorBoardMetricsWriter<W> {
+    fn write_scalars(
+        &mut self,
+        group: &str,
+        step: u64,
+        scalars: &[Scalar],
+    ) -> Result<(), Error> {
+        let mut summary = vec![];
+        for (name, value) in scalars {
+            let Some(value) = value else {
+                continue;
+            };
+
+            let mut summary_value = vec![];
+            encode_bytes_field(
+                &mut summary_value,
+                1,
+                format!("{group}/{name}").as_bytes(),
+            );
+            encode_key(&mut summary_value, 2, 5);
+            summary_value.extend((*value as f32).to_le_bytes());
+            encode_bytes_field(&mut summary, 1, &summary_value);
+        }
+
+        let mut event = encode_event_header(get_wall_time(), step);
+        encode_bytes_field(&mut event, 5, &summary);
+        self.write_event(&event)?;
+
+        Ok(())
+    }
+
+    fn write_image(
+        &mut self,
+        group: &str,
+        tag: &str,
+        step: u64,
+        image: &RgbImage,
+    ) -> Result<(), Error> {
+        let mut image_encoded = vec![];
+        image.write_to(
+            &mut Cursor::new(&mut image_encoded),
+            image::ImageFormat::Png,
+        )?;
+
+        let mut summary_image = vec![];
+        encode_varint_field(&mut summary_image, 1, image.height() as u64);
+        encode_varint_field(&mut summary_image, 2, image.width() as u64);
+        encode_varint_field(&mut summary_image, 3, 3);
+        encode_bytes_field(&mut summary_image, 4, &image_encoded);
+
+        let mut summary_value = vec![];
+        encode_bytes_field(&mut summary_value, 1, format!("{group}/{tag}").as_bytes());
+        encode_bytes_field(&mut summary_value, 4, &summary_image);
+
+        let mut summary = vec![];
+        encode_bytes_field(&mut summary, 1, &summary_value);
+
+        let mut event = encode_event_header(get_wall_time(), step);
+        encode_bytes_field(&mut event, 5, &summary);
+        self.write_event(&event)?;
+
+        Ok(())
+    }
+}
+
+/// Compute the CRC-32C (Castagnoli) of the bytes.
+fn crc32c(bytes: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut j = 0;
+            while j < 8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0x82F6_3B78
+                } else {
+                    crc >> 1
+                };
+                j += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    !bytes.iter().fold(!0, |crc, byte| {
+        TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Mask the CRC-32C as in the TensorBoard records.
+#[inline]
+fn mask_crc32c(crc: u32) -> u32 {
+    crc.rotate_right(15).wrapping_add(0xA282_EAD8)
+}
+
+/// Encode the wall time (field 1) and the step (field 2) of an event.
+fn encode_event_header(
+    wall_time: f64,
+    step: u64,
+) -> Vec<u8> {
+    let mut event = vec![];
+    encode_key(&mut event, 1, 1);
+    event.extend(wall_time.to_le_bytes());
+    encode_varint_field(&mut event, 2, step);
+    event
+}
+
+#[inline]
+fn encode_bytes_field(
+    message: &mut Vec<u8>,
+    field: u32,
+    bytes: &[u8],
+) {
+    encode_key(message, field, 2);
+    encode_varint(message, bytes.len() as u64);
+    message.extend_from_slice(bytes);
+}
+
+#[inline]
+fn encode_varint_field(
+    message: &mut Vec<u8>,
+    field: u32,
+    value: u64,
+) {
+    encode_key(message, field, 0);
+    encode_varint(message, value);
+}
+
+#[inline]
+fn encode_key(
+    message: &mut Vec<u8>,
+    field: u32,
+    wire_type: u32,
+) {
+    encode_varint(message, ((field << 3) | wire_type) as u64);
+}
+
+fn encode_varint(
+    message: &mut Vec<u8>,
+    mut value: u64,
+) {
+    while value >= 0x80 {
+        message.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    message.push(value as u8);
+}
+
+#[inline]
+fn get_wall_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn crc32c() {
+        let target = 0xE3069283;
+        let output = super::crc32c(b"123456789");
+        assert_eq!(output, target);
+
+        let target = 0;
+        let output = super::crc32c(b"");
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn encode_varint() {
+        let mut output = vec![];
+        super::encode_varint(&mut output, 300);
+
+        let target = vec![0xAC, 0x02];
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn write_scalars() {
+        use super::*;
+
+        let mut writer = TensorBoardMetricsWriter::new(vec![]).unwrap();
+        writer
+            .write_scalars(
+                GROUP_TRAIN,
+                300,
+                &[("loss", Some(0.5)), ("point_count_cloned", None)],
+            )
+            .unwrap();
+
+        let records = read_records(&writer.writer);
+        assert_eq!(records.len(), 2);
+
+        let record = &records[0];
+        let target = TENSORBOARD_FILE_VERSION.as_bytes();
+        let output = &record[record.len() - target.len()..];
+        assert_eq!(output, target);
+
+        // Wall time, step (300), summary with a value of the tag and the scalar
+        let record = &records[1];
+        let tag = b"train/loss";
+        let mut target = vec![0x10, 0xAC, 0x02, 0x2A, 19, 0x0A, 17, 0x0A, 10];
+        target.extend(tag);
+        target.push(0x15);
+        target.extend(0.5_f32.to_le_bytes());
+        let output = &record[9..];
+        assert_eq!(output, target);
+    }
+
+    #[test]
+    fn write_image() {
+        use super::*;
+
+        let image = RgbImage::from_raw(2, 1, vec![255, 0, 128, 0, 64, 32]).unwrap();
+
+        let mut writer = TensorBoardMetricsWriter::new(vec![]).unwrap();
+        writer.write_image(GROUP_PREVIEW, "7", 300, &image).unwrap();
+
+        let records = read_records(&writer.writer);
+        assert_eq!(records.len(), 2);
+
+        // Event > summary (5) > value (1) > image (4)
+        let record = &records[1];
+        let target = 300_u64.to_le_bytes().to_vec();
+        let output = get_field(record, 2);
+        assert_eq!(output, target);
+
+        let summary_value = get_field(&get_field(record, 5), 1);
+        let target = b"preview/7".to_vec();
+        let output = get_field(&summary_value, 1);
+        assert_eq!(output, target);
+
+        let summary_image = get_field(&summary_value, 4);
+        let target = 1_u64.to_le_bytes().to_vec();
+        let output = get_field(&summary_image, 1);
+        assert_eq!(output, target);
+        let target = 2_u64.to_le_bytes().to_vec();
+        let output = get_field(&summary_image, 2);
+        assert_eq!(output, target);
+
+        let target = image;
+        let output = image::load_from_memory(&get_field(&summary_image, 4))
+            .unwrap()
+            .into_rgb8();
+        assert_eq!(output, target);
+    }
+
+    /// Read the data of the records, checking the CRCs.
+    fn read_records(mut bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut records = vec![];
+        while !bytes.is_empty() {
+            let len = u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize;
+            let crc_len = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+            let data = &bytes[12..12 + len];
+            let crc_data =
+                u32::from_le_bytes(bytes[12 + len..16 + len].try_into().unwrap());
+            assert_eq!(crc_len, super::mask_crc32c(super::crc32c(&bytes[..8])));
+            assert_eq!(crc_data, super::mask_crc32c(super::crc32c(data)));
+            records.push(data.to_vec());
+            bytes = &bytes[16 + len..];
+        }
+        records
+    }
+
+    /// Get the payload of the first field in the message.
+    ///
+    /// The varints are decoded into little-endian bytes of `u64`.
+    fn get_field(
+        mut message: &[u8],
+        field: u64,
+    ) -> Vec<u8> {
+        while !message.is_empty() {
+            let key = decode_varint(&mut message);
+            let len = match key & 7 {
+                0 => {
+                    let value = decode_varint(&mut message).to_le_bytes().to_vec();
+                    if key >> 3 == field {
+                        return value;
+                    }
+                    continue;
+                },
+                1 => 8,
+                2 => decode_varint(&mut message) as usize,
+                5 => 4,
+                wire_type => panic!("Unknown wire type: {wire_type}"),
+            };
+            let (payload, rest) = message.split_at(len);
+            if key >> 3 == field {
+                return payload.to_vec();
+            }
+            message = rest;
+        }
+        panic!("Missing field: {field}");
+    }
+
+    /// Decode a varint from the front of the bytes.
+    fn decode_varint(bytes: &mut &[u8]) -> u64 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = bytes[0];
+            *bytes = &bytes[1..];
+            value |= ((byte & 0x7F) as u64) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+        }
+    }
+}
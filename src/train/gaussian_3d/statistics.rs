@@ -0,0 +1,90 @@
+//! 3DGS trainer statistics.
+
+pub use super::*;
+
+use std::time::Duration;
+
+/// Statistics of a training iteration.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Gaussian3dTrainerStatistics {
+    /// Count of cameras in the batch.
+    pub camera_count: usize,
+    /// Max degree of colors SH after the iteration.
+    pub colors_sh_degree_max: u32,
+    /// Densification in the iteration.
+    pub densification: Option<DensificationStatistics>,
+    /// Duration of the iteration.
+    pub duration: Duration,
+    /// Iteration.
+    pub iteration: u64,
+    /// Learning rate for colors SH used in the iteration.
+    pub learning_rate_colors_sh: f64,
+    /// Learning rate for opacities used in the iteration.
+    pub learning_rate_opacities: f64,
+    /// Learning rate for positions used in the iteration.
+    pub learning_rate_positions: f64,
+    /// Learning rate for rotations used in the iteration.
+    pub learning_rate_rotations: f64,
+    /// Learning rate for scalings used in the iteration.
+    pub learning_rate_scalings: f64,
+    /// Loss for colors RGB averaged over the batch.
+    ///
+    /// It is read only in the range for loss statistics.
+    pub loss: Option<f64>,
+    /// Count of points after the iteration.
+    pub point_count: usize,
+}
+
+/// Statistics of a densification.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DensificationStatistics {
+    /// Count of points cloned.
+    pub point_count_cloned: usize,
+    /// Count of points retained.
+    pub point_count_retained: usize,
+    /// Count of points split.
+    ///
+    /// Each selected point is split into two.
+    pub point_count_splitted: usize,
+}
+
+impl DensificationStatistics {
+    /// Return the count of points after the densification.
+    #[inline]
+    pub fn point_count(&self) -> usize {
+        self.point_count_retained + self.point_count_cloned + self.point_count_splitted
+    }
+}
+
+impl Gaussian3dTrainerStatistics {
+    /// Load the learning rates from the trainer.
+    pub fn with_learning_rates<AB: AutodiffBackend>(
+        mut self,
+        trainer: &Gaussian3dTrainer<AB>,
+    ) -> Self {
+        self.learning_rate_colors_sh = *trainer.learning_rate_colors_sh;
+        self.learning_rate_opacities = *trainer.learning_rate_opacities;
+        self.learning_rate_positions = *trainer.learning_rate_positions;
+        self.learning_rate_rotations = *trainer.learning_rate_rotations;
+        self.learning_rate_scalings = *trainer.learning_rate_scalings;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn point_count() {
+        use super::*;
+
+        let densification = DensificationStatistics {
+            point_count_cloned: 3,
+            point_count_retained: 10,
+            point_count_splitted: 4,
+        };
+
+        let target = 17;
+        let output = densification.point_count();
+        assert_eq!(output, target);
+    }
+}